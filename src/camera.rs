@@ -1,6 +1,17 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::core::FrameCount;
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::pbr::{DirectionalLightShadowMap, ScreenSpaceAmbientOcclusionBundle};
 use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
 use bevy_flycam::prelude::*;
 
+use crate::robot::{Robot, RobotBounds};
+use crate::segmentation::SegmentationCamera;
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
@@ -10,14 +21,165 @@ impl Plugin for CameraPlugin {
                 sensitivity: 0.0002,
                 speed: 2.0,
             })
-            .add_systems(Startup, spawn_camera);
+            .init_resource::<CameraMode>()
+            .init_resource::<OrbitFocus>()
+            .init_resource::<FollowTarget>()
+            .init_resource::<FollowSettings>()
+            .init_resource::<ScreenshotSettings>()
+            .init_resource::<CameraConfig>()
+            .init_resource::<RenderQuality>()
+            .add_systems(Startup, (spawn_camera, apply_render_quality).chain())
+            .add_systems(Update, take_screenshot)
+            .add_systems(
+                Update,
+                auto_frame_robot.run_if(|config: Res<CameraConfig>| config.auto_frame),
+            )
+            .add_systems(
+                Update,
+                (
+                    default_orbit_focus,
+                    toggle_camera_mode,
+                    focus_orbit_camera,
+                    orbit_camera.run_if(resource_equals(CameraMode::Orbit)),
+                    follow_camera.run_if(resource_equals(CameraMode::Follow)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Where the camera starts and its vertical field of view in radians. With
+/// `auto_frame`, the camera backs off along the same viewing direction to fit
+/// the first robot once its meshes have loaded.
+#[derive(Resource)]
+pub struct CameraConfig {
+    pub position: Vec3,
+    pub look_at: Vec3,
+    pub fov: f32,
+    pub auto_frame: bool,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(-1.0, 1.0, 2.0),
+            look_at: Vec3::ZERO,
+            fov: PerspectiveProjection::default().fov,
+            auto_frame: false,
+        }
+    }
+}
+
+/// Anti-aliasing, shadow and ambient occlusion settings, applied when the
+/// camera spawns. `msaa` is the sample count (1 disables it). Bevy's SSAO
+/// doesn't support MSAA, so enabling `ambient_occlusion` turns MSAA off.
+#[derive(Resource)]
+pub struct RenderQuality {
+    pub msaa: u8,
+    pub shadow_map_size: u32,
+    pub ambient_occlusion: bool,
+}
+
+impl Default for RenderQuality {
+    fn default() -> Self {
+        Self {
+            msaa: Msaa::default().samples() as u8,
+            shadow_map_size: DirectionalLightShadowMap::default().size as u32,
+            ambient_occlusion: false,
+        }
+    }
+}
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    #[default]
+    Fly,
+    Orbit,
+    Follow,
+}
+
+/// Entity the orbit camera rotates around. Defaults to the first spawned
+/// robot; set it to any link entity to orbit that link instead.
+#[derive(Resource, Default)]
+pub struct OrbitFocus(pub Option<Entity>);
+
+/// Entity the follow camera tracks. Falls back to the orbit focus when unset.
+#[derive(Resource, Default)]
+pub struct FollowTarget(pub Option<Entity>);
+
+/// The follow camera sits at the target's position plus `offset` (in world
+/// space) and closes the gap at a rate set by `smoothing`, in 1/s.
+#[derive(Resource)]
+pub struct FollowSettings {
+    pub offset: Vec3,
+    pub smoothing: f32,
+}
+
+impl Default for FollowSettings {
+    fn default() -> Self {
+        Self {
+            offset: Vec3::new(-0.5, 0.4, 0.5),
+            smoothing: 4.0,
+        }
+    }
+}
+
+/// Directory F12 screenshots are written to.
+#[derive(Resource)]
+pub struct ScreenshotSettings {
+    pub directory: PathBuf,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("screenshots"),
+        }
+    }
+}
+
+#[derive(Component)]
+struct OrbitCamera {
+    focus: Vec3,
+    radius: f32,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl OrbitCamera {
+    fn from_transform(transform: &Transform, focus: Vec3) -> Self {
+        let offset = transform.translation - focus;
+        let radius = offset.length().max(0.01);
+
+        Self {
+            focus,
+            radius,
+            yaw: offset.x.atan2(offset.z),
+            pitch: -(offset.y / radius).clamp(-1.0, 1.0).asin(),
+        }
+    }
+
+    fn apply(&self, transform: &mut Transform) {
+        let rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0);
+        transform.rotation = rotation;
+        transform.translation = self.focus + rotation * Vec3::Z * self.radius;
     }
 }
 
-fn spawn_camera(mut commands: Commands) {
+/// Query filter for the 3D cameras that draw to the window, leaving out
+/// segmentation cameras.
+pub type MainCamera = (With<Camera3d>, Without<SegmentationCamera>);
+
+fn spawn_camera(mut commands: Commands, config: Res<CameraConfig>) {
     let camera = (
         Camera3dBundle {
-            transform: Transform::from_xyz(-1.0, 1.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y),
+            transform: Transform::from_translation(config.position)
+                .looking_at(config.look_at, Vec3::Y),
+            projection: PerspectiveProjection {
+                fov: config.fov,
+                ..Default::default()
+            }
+            .into(),
             ..Default::default()
         },
         FlyCam,
@@ -25,3 +187,404 @@ fn spawn_camera(mut commands: Commands) {
 
     commands.spawn(camera);
 }
+
+fn apply_render_quality(
+    mut commands: Commands,
+    quality: Res<RenderQuality>,
+    cameras: Query<Entity, MainCamera>,
+) {
+    let msaa = match quality.msaa {
+        _ if quality.ambient_occlusion => Msaa::Off,
+        1 => Msaa::Off,
+        2 => Msaa::Sample2,
+        4 => Msaa::Sample4,
+        8 => Msaa::Sample8,
+        samples => {
+            warn!("Unsupported MSAA sample count {samples}, expected 1, 2, 4 or 8");
+            Msaa::default()
+        }
+    };
+    if quality.ambient_occlusion && quality.msaa > 1 {
+        warn!("Ambient occlusion doesn't support MSAA, disabling MSAA");
+    }
+    commands.insert_resource(msaa);
+
+    commands.insert_resource(DirectionalLightShadowMap {
+        size: quality.shadow_map_size as usize,
+    });
+
+    if quality.ambient_occlusion {
+        for camera in cameras.iter() {
+            commands
+                .entity(camera)
+                .insert(ScreenSpaceAmbientOcclusionBundle::default());
+        }
+    }
+}
+
+fn auto_frame_robot(
+    config: Res<CameraConfig>,
+    robots: Query<&RobotBounds, Added<RobotBounds>>,
+    mut cameras: Query<(&mut Transform, &Projection), MainCamera>,
+    mut framed: Local<bool>,
+) {
+    if *framed {
+        return;
+    }
+    let Some(bounds) = robots.iter().next() else {
+        return;
+    };
+
+    let center = (bounds.min + bounds.max) / 2.0;
+    let radius = (bounds.max - bounds.min).length() / 2.0;
+    let direction = (config.position - config.look_at)
+        .try_normalize()
+        .unwrap_or(Vec3::Z);
+
+    for (mut transform, projection) in cameras.iter_mut() {
+        let aspect_ratio = match projection {
+            Projection::Perspective(perspective) => perspective.aspect_ratio,
+            Projection::Orthographic(_) => 1.0,
+        };
+        let distance = framing_distance(radius, config.fov, aspect_ratio);
+        *transform =
+            Transform::from_translation(center + direction * distance).looking_at(center, Vec3::Y);
+    }
+    *framed = true;
+}
+
+/// How far from the center of a sphere of `radius` a camera with vertical
+/// field of view `fov` must be to see all of it. The sphere is fit within the
+/// narrower of the vertical and horizontal fields of view, so tall windows
+/// don't crop it.
+fn framing_distance(radius: f32, fov: f32, aspect_ratio: f32) -> f32 {
+    let half_fov = (fov / 2.0).min(((fov / 2.0).tan() * aspect_ratio).atan());
+    radius / half_fov.sin()
+}
+
+fn default_orbit_focus(mut focus: ResMut<OrbitFocus>, robots: Query<Entity, With<Robot>>) {
+    if focus.0.is_none() {
+        if let Some(robot) = robots.iter().next() {
+            focus.0 = Some(robot);
+        }
+    }
+}
+
+fn toggle_camera_mode(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<CameraMode>,
+    focus: Res<OrbitFocus>,
+    targets: Query<&GlobalTransform>,
+    cameras: Query<(Entity, &Transform), MainCamera>,
+    mut primary_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyC) {
+        return;
+    }
+
+    *mode = match *mode {
+        CameraMode::Fly => CameraMode::Orbit,
+        CameraMode::Orbit => CameraMode::Follow,
+        CameraMode::Follow => CameraMode::Fly,
+    };
+
+    for (entity, transform) in cameras.iter() {
+        match *mode {
+            CameraMode::Orbit => {
+                let focus_point = focus
+                    .0
+                    .and_then(|target| targets.get(target).ok())
+                    .map_or(Vec3::ZERO, |target| target.translation());
+
+                commands
+                    .entity(entity)
+                    .remove::<FlyCam>()
+                    .insert(OrbitCamera::from_transform(transform, focus_point));
+            }
+            CameraMode::Follow => {
+                commands.entity(entity).remove::<(FlyCam, OrbitCamera)>();
+            }
+            CameraMode::Fly => {
+                commands
+                    .entity(entity)
+                    .remove::<OrbitCamera>()
+                    .insert(FlyCam);
+            }
+        }
+    }
+
+    // Only fly mode uses the grabbed cursor; it grabs it again with Escape.
+    if *mode != CameraMode::Fly {
+        if let Ok(mut window) = primary_window.get_single_mut() {
+            window.cursor.grab_mode = CursorGrabMode::None;
+            window.cursor.visible = true;
+        }
+    }
+}
+
+fn focus_orbit_camera(
+    focus: Res<OrbitFocus>,
+    targets: Query<&GlobalTransform>,
+    mut cameras: Query<&mut OrbitCamera>,
+) {
+    if !focus.is_changed() {
+        return;
+    }
+
+    let Some(target) = focus.0.and_then(|target| targets.get(target).ok()) else {
+        return;
+    };
+
+    for mut orbit in cameras.iter_mut() {
+        orbit.focus = target.translation();
+    }
+}
+
+fn orbit_camera(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut scroll: EventReader<MouseWheel>,
+    mut cameras: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    let delta: Vec2 = motion.read().map(|event| event.delta).sum();
+    let zoom: f32 = scroll
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y * 0.01,
+        })
+        .sum();
+
+    for (mut orbit, mut transform) in cameras.iter_mut() {
+        if mouse_buttons.pressed(MouseButton::Left) {
+            orbit.yaw -= delta.x * 0.005;
+            orbit.pitch = (orbit.pitch - delta.y * 0.005).clamp(-1.54, 1.54);
+        }
+
+        if mouse_buttons.pressed(MouseButton::Middle) {
+            let pan =
+                (transform.right() * -delta.x + transform.up() * delta.y) * orbit.radius * 0.001;
+            orbit.focus += pan;
+        }
+
+        orbit.radius = (orbit.radius * (1.0 - zoom * 0.1)).max(0.01);
+        orbit.apply(&mut transform);
+    }
+}
+
+fn follow_camera(
+    time: Res<Time>,
+    target: Res<FollowTarget>,
+    focus: Res<OrbitFocus>,
+    settings: Res<FollowSettings>,
+    targets: Query<&GlobalTransform>,
+    mut cameras: Query<&mut Transform, MainCamera>,
+) {
+    let Some(target) = target
+        .0
+        .or(focus.0)
+        .and_then(|target| targets.get(target).ok())
+    else {
+        return;
+    };
+
+    let target = target.translation();
+    let blend = 1.0 - (-settings.smoothing * time.delta_seconds()).exp();
+
+    for mut transform in cameras.iter_mut() {
+        transform.translation = transform.translation.lerp(target + settings.offset, blend);
+        transform.look_at(target, Vec3::Y);
+    }
+}
+
+fn take_screenshot(
+    keys: Res<ButtonInput<KeyCode>>,
+    frame: Res<FrameCount>,
+    settings: Res<ScreenshotSettings>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+) {
+    if !keys.just_pressed(KeyCode::F12) {
+        return;
+    }
+
+    let Ok(window) = primary_window.get_single() else {
+        return;
+    };
+
+    if let Err(err) = std::fs::create_dir_all(&settings.directory) {
+        error!(
+            "Failed to create screenshot directory {}: {err}",
+            settings.directory.display()
+        );
+        return;
+    }
+
+    // The frame count keeps repeated presses within the same second apart.
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let path = settings
+        .directory
+        .join(format!("screenshot-{timestamp}-{}.png", frame.0));
+
+    if let Err(err) = screenshots.save_screenshot_to_disk(window, &path) {
+        warn!("Failed to take screenshot: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::pbr::ScreenSpaceAmbientOcclusionSettings;
+
+    use super::*;
+
+    #[test]
+    fn applies_msaa_and_shadow_map_size() {
+        let mut world = World::new();
+        world.insert_resource(RenderQuality {
+            msaa: 4,
+            shadow_map_size: 4096,
+            ambient_occlusion: false,
+        });
+        world.run_system_once(apply_render_quality);
+
+        assert_eq!(*world.resource::<Msaa>(), Msaa::Sample4);
+        assert_eq!(world.resource::<DirectionalLightShadowMap>().size, 4096);
+    }
+
+    #[test]
+    fn ambient_occlusion_turns_msaa_off() {
+        let mut world = World::new();
+        world.insert_resource(RenderQuality {
+            msaa: 4,
+            shadow_map_size: 2048,
+            ambient_occlusion: true,
+        });
+        let camera = world.spawn(Camera3d::default()).id();
+        world.run_system_once(apply_render_quality);
+
+        assert_eq!(*world.resource::<Msaa>(), Msaa::Off);
+        assert!(world
+            .get::<ScreenSpaceAmbientOcclusionSettings>(camera)
+            .is_some());
+    }
+
+    #[test]
+    fn follow_camera_converges_on_target() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<OrbitFocus>();
+        world.init_resource::<FollowSettings>();
+        let target = world.spawn(GlobalTransform::from_xyz(2.0, 0.0, 1.0)).id();
+        world.insert_resource(FollowTarget(Some(target)));
+        let camera = world.spawn((Camera3d::default(), Transform::IDENTITY)).id();
+        let goal = Vec3::new(2.0, 0.0, 1.0) + FollowSettings::default().offset;
+
+        let mut gap = f32::INFINITY;
+        for _ in 0..100 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(50));
+            world.run_system_once(follow_camera);
+            let new_gap = world
+                .get::<Transform>(camera)
+                .unwrap()
+                .translation
+                .distance(goal);
+            assert!(new_gap <= gap);
+            gap = new_gap;
+        }
+
+        let transform = world.get::<Transform>(camera).unwrap();
+        assert!(transform.translation.abs_diff_eq(goal, 1e-4));
+        let to_target = (Vec3::new(2.0, 0.0, 1.0) - transform.translation).normalize();
+        assert!(transform.forward().abs_diff_eq(to_target, 1e-4));
+    }
+
+    #[test]
+    fn f12_queues_screenshot() {
+        let directory = std::env::temp_dir().join(format!("screenshots-{}", std::process::id()));
+        let mut world = World::new();
+        world.init_resource::<FrameCount>();
+        world.init_resource::<ScreenshotManager>();
+        world.insert_resource(ScreenshotSettings {
+            directory: directory.clone(),
+        });
+        let window = world.spawn((Window::default(), PrimaryWindow)).id();
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::F12);
+        world.insert_resource(keys);
+
+        world.run_system_once(take_screenshot);
+        let created = directory.is_dir();
+        std::fs::remove_dir_all(&directory).ok();
+
+        assert!(created);
+        // Only one screenshot per window can be pending, so this one is refused.
+        assert!(world
+            .resource_mut::<ScreenshotManager>()
+            .take_screenshot(window, |_| {})
+            .is_err());
+    }
+
+    #[test]
+    fn camera_config_places_camera() {
+        let mut world = World::new();
+        world.insert_resource(CameraConfig {
+            position: Vec3::new(3.0, 2.0, 1.0),
+            look_at: Vec3::new(0.0, 0.5, 0.0),
+            fov: 60_f32.to_radians(),
+            auto_frame: false,
+        });
+        world.run_system_once(spawn_camera);
+
+        let (transform, projection) = world
+            .query_filtered::<(&Transform, &Projection), MainCamera>()
+            .single(&world);
+        assert_eq!(transform.translation, Vec3::new(3.0, 2.0, 1.0));
+        let to_look_at = (Vec3::new(0.0, 0.5, 0.0) - transform.translation).normalize();
+        assert!(transform.forward().abs_diff_eq(to_look_at, 1e-5));
+        let Projection::Perspective(perspective) = projection else {
+            panic!("camera should have a perspective projection");
+        };
+        assert_eq!(perspective.fov, 60_f32.to_radians());
+    }
+
+    #[test]
+    fn framed_bounds_fit_in_frustum() {
+        let (min, max) = (Vec3::new(-0.2, 0.0, -0.1), Vec3::new(0.3, 0.8, 0.1));
+        let center = (min + max) / 2.0;
+        let radius = (max - min).length() / 2.0;
+        let fov = 45_f32.to_radians();
+
+        for aspect_ratio in [0.5, 1.0, 16.0 / 9.0] {
+            let distance = framing_distance(radius, fov, aspect_ratio);
+            let camera = Transform::from_translation(
+                center + Vec3::new(1.0, 0.5, 2.0).normalize() * distance,
+            )
+            .looking_at(center, Vec3::Y);
+            let to_camera = camera.compute_matrix().inverse();
+
+            for corner in 0..8 {
+                let pick = |bit: usize, axis: usize| {
+                    if corner & bit == 0 {
+                        min[axis]
+                    } else {
+                        max[axis]
+                    }
+                };
+                let corner = Vec3::new(pick(1, 0), pick(2, 1), pick(4, 2));
+                let view = to_camera.transform_point3(corner);
+                assert!(view.z < 0.0);
+                let half_height = (fov / 2.0).tan();
+                assert!((view.y / -view.z).abs() <= half_height + 1e-5);
+                assert!((view.x / -view.z).abs() <= half_height * aspect_ratio + 1e-5);
+            }
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+use crate::camera::{CameraConfig, RenderQuality};
+use crate::end_effector::EndEffector;
+use crate::ik::IkTarget;
+use crate::inertia::InvalidInertia;
+use crate::recorder::JointRecorderSettings;
+use crate::robot::{
+    HiddenLinks, LinkMaterials, MassOverrides, PackagePathResolver, SpawnOverlapCheck, UpAxis,
+};
+use crate::segmentation::SegmentationCameraConfig;
+use crate::trajectory::JointTrajectorySettings;
+use crate::world::{Background, FloorShape, LightingConfig, WorldEnvironment};
+use crate::{RobotSpawnConfigs, SimMode};
+
+pub const USAGE: &str = "\
+Usage: rover [OPTIONS]
+
+Robot:
+  --urdf <PATH>              URDF (or .xacro) to spawn
+  --count <N>                Number of copies to spawn side by side
+  --up-axis <y|z>            Up axis of the robot description [default: z]
+  --fix-inertia              Replace invalid link inertia tensors with a box
+                             inertia from the link's primitive shapes
+  --mass <LINK=KG>           Mass of this link instead of the URDF's, with
+                             its inertia scaled to match, may be repeated
+  --lift-above-floor         Raise robots that reach below the floor onto it
+  --package <NAME=PATH>      Root for package://NAME/ URIs, may be repeated
+  --hide-link <LINK>         Hide this link's visual meshes, may be repeated
+  --end-effector <LINK>      Track this link's pose
+  --print-end-effector       Log the end effector pose when it changes
+  --ik-target <X,Y,Z>        Solve joint positions to reach this point with
+                             the end effector
+
+World:
+  --floor <disk|plane|grid>  Floor shape [default: disk]
+  --background <BG>          default, studio, a .ktx2 cubemap or a hex color
+  --ambient <BRIGHTNESS>     Ambient light brightness
+
+Camera and rendering:
+  --camera-position <X,Y,Z>  Camera start position
+  --look-at <X,Y,Z>          Point the camera starts looking at
+  --fov <DEGREES>            Vertical field of view
+  --auto-frame               Frame the first robot once it has loaded, the
+                             default with --urdf and no camera position
+  --msaa <1|2|4|8>           MSAA sample count
+  --shadow-map-size <N>      Directional light shadow map resolution
+  --ssao                     Screen-space ambient occlusion
+  --segmentation-camera <WxH>
+                             Render a segmentation mask offscreen from the
+                             camera, saved with F11
+  --metallic <0-1>           Metallic factor of link materials [default: 0]
+  --roughness <0-1>          Roughness of link materials [default: 0.5]
+
+Run:
+  --headless                 Run without a window, then exit
+  --steps <N>                Updates to run in headless mode [default: 100]
+  --record <PATH>            Record joint states and link poses to a CSV
+                             file every fixed timestep
+  --play <PATH>              Play a trajectory saved with F8 on the joints
+  --loop                     Start the --play trajectory over at its end
+                             instead of holding the last waypoint
+  -h, --help                 Print this help";
+
+/// Every setting the command line can change, already in the form of the
+/// resources it configures. Options left out keep their defaults.
+pub struct Cli {
+    pub mode: SimMode,
+    pub robots: RobotSpawnConfigs,
+    pub packages: PackagePathResolver,
+    pub hidden_links: HiddenLinks,
+    pub mass_overrides: MassOverrides,
+    pub spawn_check: SpawnOverlapCheck,
+    pub link_materials: LinkMaterials,
+    pub end_effector: Option<EndEffector>,
+    pub ik_target: Option<IkTarget>,
+    pub floor: FloorShape,
+    pub environment: WorldEnvironment,
+    pub lighting: LightingConfig,
+    pub camera: CameraConfig,
+    pub quality: RenderQuality,
+    pub segmentation: SegmentationCameraConfig,
+    pub recorder: JointRecorderSettings,
+    pub trajectory: JointTrajectorySettings,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Help,
+    UnknownFlag(String),
+    MissingValue(String),
+    /// `flag` only works together with `needs`.
+    Requires {
+        flag: &'static str,
+        needs: &'static str,
+    },
+    InvalidValue {
+        flag: String,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Help => write!(f, "help requested"),
+            CliError::UnknownFlag(flag) => write!(f, "unknown option `{flag}`"),
+            CliError::MissingValue(flag) => write!(f, "`{flag}` needs a value"),
+            CliError::Requires { flag, needs } => write!(f, "`{flag}` requires `{needs}`"),
+            CliError::InvalidValue {
+                flag,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value `{value}` for `{flag}`, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl Cli {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut cli = Cli {
+            mode: SimMode::Windowed,
+            robots: RobotSpawnConfigs::default(),
+            packages: PackagePathResolver::default(),
+            hidden_links: HiddenLinks::default(),
+            mass_overrides: MassOverrides::default(),
+            spawn_check: SpawnOverlapCheck::default(),
+            link_materials: LinkMaterials::default(),
+            end_effector: None,
+            ik_target: None,
+            floor: FloorShape::default(),
+            environment: WorldEnvironment::default(),
+            lighting: LightingConfig::default(),
+            camera: CameraConfig::default(),
+            quality: RenderQuality::default(),
+            segmentation: SegmentationCameraConfig::default(),
+            recorder: JointRecorderSettings::default(),
+            trajectory: JointTrajectorySettings::default(),
+        };
+        let mut headless = false;
+        let mut steps = 100;
+        let mut print_end_effector = false;
+        let mut custom_urdf = false;
+        let mut custom_camera = false;
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| CliError::MissingValue(flag.clone()))
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => return Err(CliError::Help),
+                "--urdf" => {
+                    let urdf_path = value()?;
+                    custom_urdf = true;
+                    for robot in &mut cli.robots.0 {
+                        robot.urdf_path.clone_from(&urdf_path);
+                    }
+                }
+                "--count" => {
+                    let count = parse(&flag, value()?, "a number")?;
+                    for robot in &mut cli.robots.0 {
+                        robot.count = count;
+                    }
+                }
+                "--up-axis" => {
+                    let up_axis = match value()?.as_str() {
+                        "y" => UpAxis::Y,
+                        "z" => UpAxis::Z,
+                        other => return Err(invalid(&flag, other, "y or z")),
+                    };
+                    for robot in &mut cli.robots.0 {
+                        robot.up_axis = up_axis;
+                    }
+                }
+                "--fix-inertia" => {
+                    for robot in &mut cli.robots.0 {
+                        robot.invalid_inertia = InvalidInertia::Fix;
+                    }
+                }
+                "--mass" => {
+                    let mapping = value()?;
+                    let mass = mapping
+                        .split_once('=')
+                        .and_then(|(link, mass)| Some((link, mass.parse::<f32>().ok()?)));
+                    match mass {
+                        Some((link, mass)) if mass > 0.0 => {
+                            cli.mass_overrides.0.insert(link.to_string(), mass);
+                        }
+                        _ => return Err(invalid(&flag, &mapping, "LINK=KG with a positive mass")),
+                    }
+                }
+                "--lift-above-floor" => cli.spawn_check.lift_above_floor = true,
+                "--package" => {
+                    let mapping = value()?;
+                    let Some((name, root)) = mapping.split_once('=') else {
+                        return Err(invalid(&flag, &mapping, "NAME=PATH"));
+                    };
+                    cli.packages.0.insert(name.to_string(), root.into());
+                }
+                "--hide-link" => {
+                    cli.hidden_links.0.insert(value()?);
+                }
+                "--end-effector" => {
+                    cli.end_effector = Some(EndEffector {
+                        link: value()?,
+                        print: false,
+                    });
+                }
+                "--print-end-effector" => print_end_effector = true,
+                "--ik-target" => {
+                    cli.ik_target = Some(IkTarget::new(parse_vec3(&flag, value()?)?));
+                }
+                "--floor" => {
+                    cli.floor = match value()?.as_str() {
+                        "disk" => FloorShape::default(),
+                        "plane" => FloorShape::Plane,
+                        "grid" => FloorShape::Grid { size: 10.0 },
+                        other => return Err(invalid(&flag, other, "disk, plane or grid")),
+                    };
+                }
+                "--background" => {
+                    cli.environment.background = parse_background(&flag, &value()?)?;
+                }
+                "--ambient" => cli.lighting.ambient = parse(&flag, value()?, "a number")?,
+                "--camera-position" => {
+                    cli.camera.position = parse_vec3(&flag, value()?)?;
+                    custom_camera = true;
+                }
+                "--look-at" => {
+                    cli.camera.look_at = parse_vec3(&flag, value()?)?;
+                    custom_camera = true;
+                }
+                "--fov" => {
+                    const EXPECTED: &str = "degrees between 0 and 180";
+                    let value = value()?;
+                    let degrees: f32 = parse(&flag, value.clone(), EXPECTED)?;
+                    if degrees <= 0.0 || degrees >= 180.0 {
+                        return Err(invalid(&flag, &value, EXPECTED));
+                    }
+                    cli.camera.fov = degrees.to_radians();
+                }
+                "--auto-frame" => cli.camera.auto_frame = true,
+                "--msaa" => {
+                    let value = value()?;
+                    cli.quality.msaa = match value.as_str() {
+                        "1" | "2" | "4" | "8" => parse(&flag, value, "1, 2, 4 or 8")?,
+                        _ => return Err(invalid(&flag, &value, "1, 2, 4 or 8")),
+                    };
+                }
+                "--shadow-map-size" => {
+                    cli.quality.shadow_map_size = parse(&flag, value()?, "a number")?;
+                }
+                "--ssao" => cli.quality.ambient_occlusion = true,
+                "--segmentation-camera" => {
+                    cli.segmentation.size = Some(parse_size(&flag, value()?)?);
+                }
+                "--metallic" => cli.link_materials.default.metallic = parse_unit(&flag, value()?)?,
+                "--roughness" => {
+                    cli.link_materials.default.roughness = parse_unit(&flag, value()?)?;
+                }
+                "--headless" => headless = true,
+                "--steps" => steps = parse(&flag, value()?, "a number")?,
+                "--record" => cli.recorder.path = Some(value()?.into()),
+                "--play" => cli.trajectory.path = Some(value()?.into()),
+                "--loop" => cli.trajectory.looping = true,
+                _ => return Err(CliError::UnknownFlag(flag)),
+            }
+        }
+
+        match &mut cli.end_effector {
+            Some(end_effector) => end_effector.print = print_end_effector,
+            None if print_end_effector => {
+                return Err(CliError::Requires {
+                    flag: "--print-end-effector",
+                    needs: "--end-effector",
+                })
+            }
+            None => {}
+        }
+        if cli.ik_target.is_some() && cli.end_effector.is_none() {
+            return Err(CliError::Requires {
+                flag: "--ik-target",
+                needs: "--end-effector",
+            });
+        }
+        if cli.trajectory.looping && cli.trajectory.path.is_none() {
+            return Err(CliError::Requires {
+                flag: "--loop",
+                needs: "--play",
+            });
+        }
+        // The default camera pose suits the sample robot; other robots are
+        // framed once they load unless the camera was placed explicitly.
+        if custom_urdf && !custom_camera {
+            cli.camera.auto_frame = true;
+        }
+        if headless {
+            cli.mode = SimMode::Headless { steps };
+        }
+
+        Ok(cli)
+    }
+}
+
+fn invalid(flag: &str, value: &str, expected: &'static str) -> CliError {
+    CliError::InvalidValue {
+        flag: flag.to_string(),
+        value: value.to_string(),
+        expected,
+    }
+}
+
+fn parse<T: FromStr>(flag: &str, value: String, expected: &'static str) -> Result<T, CliError> {
+    value.parse().map_err(|_| invalid(flag, &value, expected))
+}
+
+/// Parses a factor between 0 and 1.
+fn parse_unit(flag: &str, value: String) -> Result<f32, CliError> {
+    const EXPECTED: &str = "a number from 0 to 1";
+    let factor: f32 = parse(flag, value.clone(), EXPECTED)?;
+    if (0.0..=1.0).contains(&factor) {
+        Ok(factor)
+    } else {
+        Err(invalid(flag, &value, EXPECTED))
+    }
+}
+
+fn parse_vec3(flag: &str, value: String) -> Result<Vec3, CliError> {
+    let components: Vec<f32> = value
+        .split(',')
+        .map(|component| component.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid(flag, &value, "X,Y,Z"))?;
+
+    match components[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(invalid(flag, &value, "X,Y,Z")),
+    }
+}
+
+fn parse_size(flag: &str, value: String) -> Result<UVec2, CliError> {
+    let size = value
+        .split_once('x')
+        .and_then(|(width, height)| Some(UVec2::new(width.parse().ok()?, height.parse().ok()?)));
+
+    match size {
+        Some(size) if size.min_element() > 0 => Ok(size),
+        _ => Err(invalid(flag, &value, "WIDTHxHEIGHT")),
+    }
+}
+
+fn parse_background(flag: &str, value: &str) -> Result<Background, CliError> {
+    match value {
+        "default" => Ok(WorldEnvironment::default().background),
+        "studio" => Ok(Background::Gradient {
+            top: Color::srgb(0.92, 0.92, 0.94),
+            bottom: Color::srgb(0.35, 0.35, 0.38),
+        }),
+        path if path.ends_with(".ktx2") => Ok(Background::Skybox {
+            path: path.to_string(),
+            brightness: 1000.0,
+        }),
+        color => Srgba::hex(color)
+            .map(|color| Background::Solid(color.into()))
+            .map_err(|_| invalid(flag, color, "default, studio, a .ktx2 path or a hex color")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Cli, CliError> {
+        Cli::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn parse_error(args: &[&str]) -> CliError {
+        match parse_args(args) {
+            Ok(_) => panic!("{args:?} parsed"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_flag() {
+        assert!(matches!(
+            parse_error(&["--frobnicate"]),
+            CliError::UnknownFlag(flag) if flag == "--frobnicate"
+        ));
+    }
+
+    #[test]
+    fn rejects_missing_value() {
+        assert!(matches!(
+            parse_error(&["--urdf"]),
+            CliError::MissingValue(flag) if flag == "--urdf"
+        ));
+    }
+
+    #[test]
+    fn parses_mass_overrides() {
+        let cli = parse_args(&["--mass", "base=2.5", "--mass", "gripper=0.1"])
+            .ok()
+            .unwrap();
+        assert_eq!(cli.mass_overrides.0["base"], 2.5);
+        assert_eq!(cli.mass_overrides.0["gripper"], 0.1);
+
+        for value in ["base", "base=heavy", "base=0"] {
+            assert!(matches!(
+                parse_error(&["--mass", value]),
+                CliError::InvalidValue { flag, .. } if flag == "--mass"
+            ));
+        }
+    }
+
+    #[test]
+    fn loop_requires_play() {
+        assert!(matches!(
+            parse_error(&["--loop"]),
+            CliError::Requires { flag: "--loop", .. }
+        ));
+        let cli = parse_args(&["--play", "wave.json", "--loop"]).ok().unwrap();
+        assert!(cli.trajectory.looping);
+    }
+
+    #[test]
+    fn rejects_bad_vec3() {
+        for value in ["1,2", "1,2,3,4", "1,x,3"] {
+            assert!(matches!(
+                parse_error(&["--look-at", value]),
+                CliError::InvalidValue { flag, .. } if flag == "--look-at"
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_msaa() {
+        assert!(matches!(
+            parse_error(&["--msaa", "3"]),
+            CliError::InvalidValue { flag, .. } if flag == "--msaa"
+        ));
+        assert_eq!(parse_args(&["--msaa", "8"]).ok().unwrap().quality.msaa, 8);
+    }
+
+    #[test]
+    fn parses_segmentation_camera_size() {
+        let cli = parse_args(&["--segmentation-camera", "640x480"])
+            .ok()
+            .unwrap();
+        assert_eq!(cli.segmentation.size, Some(UVec2::new(640, 480)));
+        for value in ["640", "0x480", "640x"] {
+            assert!(matches!(
+                parse_error(&["--segmentation-camera", value]),
+                CliError::InvalidValue { flag, .. } if flag == "--segmentation-camera"
+            ));
+        }
+    }
+
+    #[test]
+    fn ik_target_requires_end_effector() {
+        let err = parse_error(&["--ik-target", "0,0,0"]);
+        assert!(matches!(
+            err,
+            CliError::Requires {
+                flag: "--ik-target",
+                needs: "--end-effector"
+            }
+        ));
+        assert_eq!(err.to_string(), "`--ik-target` requires `--end-effector`");
+    }
+
+    #[test]
+    fn print_end_effector_requires_end_effector() {
+        assert!(matches!(
+            parse_error(&["--print-end-effector"]),
+            CliError::Requires {
+                flag: "--print-end-effector",
+                needs: "--end-effector"
+            }
+        ));
+        let cli = parse_args(&["--end-effector", "gripper", "--print-end-effector"])
+            .ok()
+            .unwrap();
+        assert!(cli.end_effector.unwrap().print);
+    }
+
+    #[test]
+    fn rejects_fov_out_of_range() {
+        for value in ["0", "-10", "180", "270"] {
+            assert!(matches!(
+                parse_error(&["--fov", value]),
+                CliError::InvalidValue { flag, .. } if flag == "--fov"
+            ));
+        }
+        let cli = parse_args(&["--fov", "90"]).ok().unwrap();
+        assert_eq!(cli.camera.fov, std::f32::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn urdf_turns_on_auto_frame() {
+        let cli = parse_args(&["--urdf", "robot.urdf"]).ok().unwrap();
+        assert!(cli.camera.auto_frame);
+
+        let cli = parse_args(&["--urdf", "robot.urdf", "--look-at", "0,0,0"])
+            .ok()
+            .unwrap();
+        assert!(!cli.camera.auto_frame);
+
+        assert!(!parse_args(&[]).ok().unwrap().camera.auto_frame);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::robot::RobotJoint;
+
+/// Lets external programs read and set joint positions over TCP. Only built
+/// with the `control_server` feature.
+///
+/// Each request is one line of JSON and gets one line of JSON back:
+///
+/// ```text
+/// {"command": "get_joints"}
+/// {"joints": {"low_cost_robot/joint1": 0.0, ...}}
+/// {"command": "set_joints", "positions": {"low_cost_robot/joint1": 0.5}}
+/// {"ok": true}
+/// ```
+///
+/// Joints are keyed by `RobotJoint::name`, and positions are clamped to the
+/// joint limits.
+///
+/// This is plain TCP, not WebSocket: there is no HTTP upgrade handshake and
+/// no message framing beyond the newline, so WebSocket clients can't connect.
+/// From Python, write lines to a `socket.create_connection` and read them
+/// back with `makefile().readline()`.
+pub struct ControlServerPlugin;
+
+impl Plugin for ControlServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControlServerSettings>()
+            .add_systems(Startup, start_control_server)
+            .add_systems(Update, handle_control_requests);
+    }
+}
+
+#[derive(Resource)]
+pub struct ControlServerSettings {
+    pub address: String,
+}
+
+impl Default for ControlServerSettings {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:9090".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request {
+    GetJoints,
+    SetJoints { positions: BTreeMap<String, f32> },
+}
+
+/// A request from a client connection and where to send its response.
+struct PendingRequest {
+    request: Request,
+    reply: Sender<Value>,
+}
+
+/// Requests from every client, drained once per frame.
+#[derive(Resource)]
+struct ControlRequests(Mutex<Receiver<PendingRequest>>);
+
+fn start_control_server(mut commands: Commands, settings: Res<ControlServerSettings>) {
+    let listener = match TcpListener::bind(&settings.address) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(
+                "Failed to start control server on {}: {err}",
+                settings.address
+            );
+            return;
+        }
+    };
+    info!("Control server listening on {}", settings.address);
+    commands.insert_resource(accept_clients(listener));
+}
+
+/// Serves every client connecting to `listener` on a thread of its own.
+fn accept_clients(listener: TcpListener) -> ControlRequests {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let sender = sender.clone();
+            std::thread::spawn(move || serve_client(stream, sender));
+        }
+    });
+    ControlRequests(Mutex::new(receiver))
+}
+
+/// Answers one client's requests until it disconnects or the app exits.
+fn serve_client(stream: TcpStream, requests: Sender<PendingRequest>) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => {
+                let (reply, response) = mpsc::channel();
+                if requests.send(PendingRequest { request, reply }).is_err() {
+                    return;
+                }
+                match response.recv() {
+                    Ok(response) => response,
+                    Err(_) => return,
+                }
+            }
+            Err(err) => json!({ "error": err.to_string() }),
+        };
+
+        if writeln!(writer, "{response}").is_err() {
+            return;
+        }
+    }
+}
+
+fn handle_control_requests(
+    requests: Option<Res<ControlRequests>>,
+    mut joints: Query<&mut RobotJoint>,
+) {
+    let Some(requests) = requests else {
+        return;
+    };
+    let Ok(receiver) = requests.0.lock() else {
+        return;
+    };
+
+    for PendingRequest { request, reply } in receiver.try_iter() {
+        let response = match request {
+            Request::GetJoints => {
+                let positions: BTreeMap<_, _> = joints
+                    .iter()
+                    .filter(|joint| joint.is_movable())
+                    .map(|joint| (joint.name.clone(), joint.position))
+                    .collect();
+                json!({ "joints": positions })
+            }
+            Request::SetJoints { positions } => {
+                let mut unknown: Vec<_> = positions.keys().cloned().collect();
+                for mut joint in &mut joints {
+                    let Some(&position) = positions.get(&joint.name) else {
+                        continue;
+                    };
+                    unknown.retain(|name| *name != joint.name);
+                    if joint.is_movable() {
+                        joint.position = joint.clamp_position(position);
+                    }
+                }
+                if unknown.is_empty() {
+                    json!({ "ok": true })
+                } else {
+                    json!({ "error": format!("unknown joints: {}", unknown.join(", ")) })
+                }
+            }
+        };
+        // The client may have disconnected while waiting.
+        let _ = reply.send(response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::ecs::system::RunSystemOnce;
+    use urdf_rs::JointType;
+
+    use super::*;
+
+    fn request(stream: &mut TcpStream, reader: &mut impl BufRead, line: &str) -> Value {
+        writeln!(stream, "{line}").unwrap();
+        let mut response = String::new();
+        reader.read_line(&mut response).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn client_sets_and_reads_joints() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let mut world = World::new();
+        world.insert_resource(accept_clients(listener));
+        let joint = world
+            .spawn(RobotJoint {
+                name: "arm/elbow".to_string(),
+                joint_type: JointType::Revolute,
+                axis: Vec3::Y,
+                lower: -1.0,
+                upper: 1.0,
+                position: 0.0,
+            })
+            .id();
+
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let set = request(
+                &mut stream,
+                &mut reader,
+                r#"{"command": "set_joints", "positions": {"arm/elbow": 2.0}}"#,
+            );
+            let get = request(&mut stream, &mut reader, r#"{"command": "get_joints"}"#);
+            (set, get)
+        });
+
+        // Stands in for the frames answering the client's requests.
+        let started = Instant::now();
+        while !client.is_finished() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "client timed out"
+            );
+            world.run_system_once(handle_control_requests);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let (set, get) = client.join().unwrap();
+        assert_eq!(set, json!({ "ok": true }));
+        assert_eq!(get, json!({ "joints": { "arm/elbow": 1.0 } }));
+        assert_eq!(world.get::<RobotJoint>(joint).unwrap().position, 1.0);
+    }
+}
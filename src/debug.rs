@@ -0,0 +1,464 @@
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use urdf_rs::JointType;
+
+use crate::robot::{InertiaEllipsoid, RobotJoint, RobotLink};
+use crate::world::FloorShape;
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JointDebugGizmos>()
+            .init_resource::<GroundGrid>()
+            .init_resource::<LinkFrameGizmos>()
+            .init_resource::<InertiaGizmos>()
+            .add_systems(
+                Update,
+                (
+                    toggle_joint_gizmos,
+                    draw_joint_gizmos.run_if(|gizmos: Res<JointDebugGizmos>| gizmos.enabled),
+                    toggle_ground_grid,
+                    draw_ground_grid.run_if(|grid: Res<GroundGrid>| grid.0),
+                    toggle_link_frames,
+                    draw_link_frames.run_if(|frames: Res<LinkFrameGizmos>| frames.enabled),
+                    toggle_inertia_gizmos,
+                    draw_inertia_ellipsoids.run_if(|inertia: Res<InertiaGizmos>| inertia.0),
+                ),
+            );
+    }
+}
+
+/// Draws each joint's axis through its anchor when enabled, an arc of
+/// `limit_arc_radius` around revolute joints spanning their limits, and the
+/// travel range of prismatic joints along their axis. Toggled with J.
+#[derive(Resource)]
+pub struct JointDebugGizmos {
+    pub enabled: bool,
+    pub limit_arc_radius: f32,
+}
+
+impl Default for JointDebugGizmos {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            limit_arc_radius: 0.03,
+        }
+    }
+}
+
+/// Draws a metric grid over the floor, 0.1 m minor and 1 m major lines, plus
+/// X/Y/Z arrows at the origin. Toggled with G.
+#[derive(Resource, Default)]
+pub struct GroundGrid(pub bool);
+
+/// Draws RGB axes at every link frame, `axis_length` long, with a line from
+/// each link to its parent link to show the kinematic tree. Toggled with F.
+#[derive(Resource)]
+pub struct LinkFrameGizmos {
+    pub enabled: bool,
+    pub axis_length: f32,
+}
+
+impl Default for LinkFrameGizmos {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            axis_length: 0.03,
+        }
+    }
+}
+
+/// Draws each link's inertia as the equivalent uniform solid ellipsoid at
+/// its center of mass, outlined by its three principal ellipses. Toggled
+/// with I.
+#[derive(Resource, Default)]
+pub struct InertiaGizmos(pub bool);
+
+const GRID_MINOR_SPACING: f32 = 0.1;
+const GRID_MAJOR_SPACING: f32 = 1.0;
+/// Past this the minor lines are too dense to read, so large floors are
+/// only covered near the origin.
+const GRID_MAX_HALF_EXTENT: f32 = 10.0;
+/// Lift above the floor so the lines don't z-fight with it.
+const GRID_HEIGHT: f32 = 0.001;
+const ORIGIN_ARROW_LENGTH: f32 = 0.2;
+
+const JOINT_AXIS_LENGTH: f32 = 0.05;
+const FIXED_JOINT_SIZE: f32 = 0.01;
+const LIMIT_ARC_SEGMENTS: usize = 32;
+
+fn toggle_joint_gizmos(keys: Res<ButtonInput<KeyCode>>, mut gizmos: ResMut<JointDebugGizmos>) {
+    if keys.just_pressed(KeyCode::KeyJ) {
+        gizmos.enabled = !gizmos.enabled;
+    }
+}
+
+fn toggle_ground_grid(keys: Res<ButtonInput<KeyCode>>, mut grid: ResMut<GroundGrid>) {
+    if keys.just_pressed(KeyCode::KeyG) {
+        grid.0 = !grid.0;
+    }
+}
+
+fn draw_ground_grid(mut gizmos: Gizmos, floor_shape: Res<FloorShape>) {
+    let half_extent = match *floor_shape {
+        FloorShape::Disk { radius } => radius,
+        FloorShape::Plane => GRID_MAX_HALF_EXTENT,
+        FloorShape::Grid { size } => size / 2.0,
+    }
+    .min(GRID_MAX_HALF_EXTENT);
+
+    let position = Vec3::Y * GRID_HEIGHT;
+    let rotation = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+    for (spacing, color) in [
+        (GRID_MINOR_SPACING, css::DIM_GRAY),
+        (GRID_MAJOR_SPACING, css::WHITE),
+    ] {
+        // Even so a line always passes through the origin.
+        let cells = (half_extent / spacing).floor() as u32 * 2;
+        gizmos.grid(
+            position,
+            rotation,
+            UVec2::splat(cells),
+            Vec2::splat(spacing),
+            color,
+        );
+    }
+
+    for (direction, color) in [
+        (Vec3::X, css::RED),
+        (Vec3::Y, css::LIME),
+        (Vec3::Z, css::BLUE),
+    ] {
+        gizmos.arrow(position, position + direction * ORIGIN_ARROW_LENGTH, color);
+    }
+}
+
+fn toggle_link_frames(keys: Res<ButtonInput<KeyCode>>, mut frames: ResMut<LinkFrameGizmos>) {
+    if keys.just_pressed(KeyCode::KeyF) {
+        frames.enabled = !frames.enabled;
+    }
+}
+
+/// A link frame to draw, with the origin of the parent link to connect it to,
+/// if the parent is a link rather than the robot root.
+struct LinkFrame {
+    transform: GlobalTransform,
+    parent_origin: Option<Vec3>,
+}
+
+fn link_frames(
+    links: &Query<(&GlobalTransform, &Parent), With<RobotLink>>,
+    link_transforms: &Query<&GlobalTransform, With<RobotLink>>,
+) -> Vec<LinkFrame> {
+    links
+        .iter()
+        .map(|(transform, parent)| LinkFrame {
+            transform: *transform,
+            parent_origin: link_transforms
+                .get(parent.get())
+                .ok()
+                .map(GlobalTransform::translation),
+        })
+        .collect()
+}
+
+fn draw_link_frames(
+    mut gizmos: Gizmos,
+    settings: Res<LinkFrameGizmos>,
+    links: Query<(&GlobalTransform, &Parent), With<RobotLink>>,
+    link_transforms: Query<&GlobalTransform, With<RobotLink>>,
+) {
+    for frame in link_frames(&links, &link_transforms) {
+        gizmos.axes(frame.transform, settings.axis_length);
+
+        if let Some(parent_origin) = frame.parent_origin {
+            gizmos.line(
+                parent_origin,
+                frame.transform.translation(),
+                css::LIGHT_GRAY,
+            );
+        }
+    }
+}
+
+fn toggle_inertia_gizmos(keys: Res<ButtonInput<KeyCode>>, mut inertia: ResMut<InertiaGizmos>) {
+    if keys.just_pressed(KeyCode::KeyI) {
+        inertia.0 = !inertia.0;
+    }
+}
+
+fn draw_inertia_ellipsoids(
+    mut gizmos: Gizmos,
+    links: Query<(&GlobalTransform, &InertiaEllipsoid)>,
+) {
+    for (transform, ellipsoid) in links.iter() {
+        let (_, rotation, center) = transform
+            .mul_transform(ellipsoid.frame)
+            .to_scale_rotation_translation();
+        let Vec3 { x: a, y: b, z: c } = ellipsoid.semi_axes;
+
+        // Ellipses are drawn in the XY plane of their rotation.
+        gizmos.ellipse(center, rotation, Vec2::new(a, b), css::TOMATO);
+        gizmos.ellipse(
+            center,
+            rotation * Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+            Vec2::new(a, c),
+            css::TOMATO,
+        );
+        gizmos.ellipse(
+            center,
+            rotation * Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            Vec2::new(c, b),
+            css::TOMATO,
+        );
+    }
+}
+
+fn draw_joint_gizmos(
+    mut gizmos: Gizmos,
+    settings: Res<JointDebugGizmos>,
+    joints: Query<(&GlobalTransform, &RobotJoint)>,
+) {
+    for (transform, joint) in joints.iter() {
+        let anchor = transform.translation();
+        let axis = transform
+            .affine()
+            .transform_vector3(joint.axis)
+            .normalize_or_zero();
+        let half_axis = axis * JOINT_AXIS_LENGTH / 2.0;
+
+        match joint.joint_type {
+            JointType::Revolute => {
+                gizmos.line(anchor - half_axis, anchor + half_axis, css::YELLOW);
+                draw_limit_arc(
+                    &mut gizmos,
+                    transform,
+                    joint,
+                    settings.limit_arc_radius,
+                    joint.lower..=joint.upper,
+                );
+                if let Some((lower, upper)) =
+                    limit_arc_endpoints(transform, joint, settings.limit_arc_radius)
+                {
+                    gizmos.line(anchor, lower, css::ORANGE);
+                    gizmos.line(anchor, upper, css::ORANGE);
+                }
+            }
+            JointType::Continuous => {
+                gizmos.line(anchor - half_axis, anchor + half_axis, css::YELLOW);
+                draw_limit_arc(
+                    &mut gizmos,
+                    transform,
+                    joint,
+                    settings.limit_arc_radius,
+                    -std::f32::consts::PI..=std::f32::consts::PI,
+                );
+            }
+            JointType::Prismatic => {
+                gizmos.line(anchor - half_axis, anchor + half_axis, css::AQUA);
+                draw_travel_range(&mut gizmos, transform, joint, settings.limit_arc_radius);
+            }
+            JointType::Fixed => {
+                for direction in [Vec3::X, Vec3::Y, Vec3::Z] {
+                    let offset = direction * FIXED_JOINT_SIZE / 2.0;
+                    gizmos.line(anchor - offset, anchor + offset, css::WHITE);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Draws the arc swept by a reference spoke turning through `range` about the
+/// joint axis, plus the spoke at the joint's current position. The arc is
+/// fixed in the joint's zero-position frame, so it doesn't turn with the link.
+fn draw_limit_arc(
+    gizmos: &mut Gizmos,
+    transform: &GlobalTransform,
+    joint: &RobotJoint,
+    radius: f32,
+    range: std::ops::RangeInclusive<f32>,
+) {
+    let Some(axis) = joint.axis.try_normalize() else {
+        return;
+    };
+
+    let points = (0..=LIMIT_ARC_SEGMENTS).filter_map(|i| {
+        let t = i as f32 / LIMIT_ARC_SEGMENTS as f32;
+        let angle = range.start() + (range.end() - range.start()) * t;
+        limit_arc_point(transform, joint, radius, angle)
+    });
+    gizmos.linestrip(points, css::ORANGE);
+
+    gizmos.line(
+        transform.translation(),
+        transform.transform_point(axis.any_orthonormal_vector() * radius),
+        css::RED,
+    );
+}
+
+/// World-space tip of the reference spoke of length `radius` with the joint
+/// at `angle`, given the child link's current `transform`. Like the arc, it
+/// is fixed in the joint's zero-position frame. `None` without an axis.
+fn limit_arc_point(
+    transform: &GlobalTransform,
+    joint: &RobotJoint,
+    radius: f32,
+    angle: f32,
+) -> Option<Vec3> {
+    let axis = joint.axis.try_normalize()?;
+    let spoke = axis.any_orthonormal_vector() * radius;
+    let from_zero = Quat::from_axis_angle(axis, angle - joint.position);
+    Some(transform.transform_point(from_zero * spoke))
+}
+
+/// Spoke tips at a revolute joint's `lower` and `upper` limits.
+fn limit_arc_endpoints(
+    transform: &GlobalTransform,
+    joint: &RobotJoint,
+    radius: f32,
+) -> Option<(Vec3, Vec3)> {
+    Some((
+        limit_arc_point(transform, joint, radius, joint.lower)?,
+        limit_arc_point(transform, joint, radius, joint.upper)?,
+    ))
+}
+
+/// Draws a prismatic joint's travel from `lower` to `upper` along its axis,
+/// plus a tick of `tick_length` at the joint's current position.
+fn draw_travel_range(
+    gizmos: &mut Gizmos,
+    transform: &GlobalTransform,
+    joint: &RobotJoint,
+    tick_length: f32,
+) {
+    let Some((lower, upper)) = travel_range_endpoints(transform, joint) else {
+        return;
+    };
+
+    gizmos.line(lower, upper, css::ORANGE);
+    gizmos.line(
+        transform.translation(),
+        transform.transform_point(joint.axis.normalize().any_orthonormal_vector() * tick_length),
+        css::RED,
+    );
+}
+
+/// World-space ends of a prismatic joint's travel, given the child link's
+/// current `transform`. The segment is fixed in the joint's zero-position
+/// frame, so it doesn't slide with the link. `None` without an axis.
+fn travel_range_endpoints(transform: &GlobalTransform, joint: &RobotJoint) -> Option<(Vec3, Vec3)> {
+    let axis = joint.axis.try_normalize()?;
+    Some((
+        transform.transform_point(axis * (joint.lower - joint.position)),
+        transform.transform_point(axis * (joint.upper - joint.position)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::ecs::world::CommandQueue;
+
+    use super::*;
+    use crate::inertia::InvalidInertia;
+    use crate::robot::{spawn_robot_from_str, MassOverrides};
+
+    #[test]
+    fn one_frame_per_link() {
+        let urdf = r#"
+            <robot name="arm">
+              <link name="base"/>
+              <link name="upper"/>
+              <link name="lower"/>
+              <joint name="shoulder" type="revolute">
+                <parent link="base"/>
+                <child link="upper"/>
+                <limit lower="-1" upper="1" effort="1" velocity="1"/>
+              </joint>
+              <joint name="elbow" type="revolute">
+                <parent link="upper"/>
+                <child link="lower"/>
+                <origin xyz="0 0 0.5"/>
+                <limit lower="-1" upper="1" effort="1" velocity="1"/>
+              </joint>
+            </robot>
+        "#;
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        spawn_robot_from_str(
+            &mut Commands::new(&mut queue, &world),
+            urdf,
+            Path::new(""),
+            Transform::IDENTITY,
+            InvalidInertia::Warn,
+            &MassOverrides::default(),
+        )
+        .unwrap();
+        queue.apply(&mut world);
+
+        let frames = world.run_system_once(
+            |links: Query<(&GlobalTransform, &Parent), With<RobotLink>>,
+             link_transforms: Query<&GlobalTransform, With<RobotLink>>| {
+                link_frames(&links, &link_transforms)
+            },
+        );
+        assert_eq!(frames.len(), 3);
+        // The base hangs off the robot root, so only two frames connect back.
+        let connected = frames
+            .iter()
+            .filter(|frame| frame.parent_origin.is_some())
+            .count();
+        assert_eq!(connected, 2);
+    }
+
+    #[test]
+    fn travel_range_ignores_joint_position() {
+        let zero_pose = Transform::from_xyz(1.0, 2.0, 3.0)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+        let mut joint = RobotJoint {
+            name: "slider".to_string(),
+            joint_type: JointType::Prismatic,
+            axis: Vec3::new(2.0, 0.0, 0.0),
+            lower: -0.5,
+            upper: 1.5,
+            position: 0.0,
+        };
+        // The unit axis in world space, after the link's rotation.
+        let axis = Vec3::Y;
+
+        for position in [-0.5, 0.0, 0.7, 1.5] {
+            joint.position = position;
+            let link = joint.link_transform(&zero_pose, position).unwrap();
+            let (lower, upper) = travel_range_endpoints(&link.into(), &joint).unwrap();
+            assert!(lower.abs_diff_eq(zero_pose.translation - 0.5 * axis, 1e-5));
+            assert!(upper.abs_diff_eq(zero_pose.translation + 1.5 * axis, 1e-5));
+        }
+    }
+
+    #[test]
+    fn limit_arc_ends_at_limits() {
+        let zero_pose = Transform::from_xyz(1.0, 2.0, 3.0);
+        let mut joint = RobotJoint {
+            name: "elbow".to_string(),
+            joint_type: JointType::Revolute,
+            axis: Vec3::new(0.0, 0.0, 3.0),
+            lower: -std::f32::consts::FRAC_PI_2,
+            upper: std::f32::consts::PI,
+            position: 0.0,
+        };
+        let spoke = Vec3::Z.any_orthonormal_vector();
+        let at = |angle: f32| zero_pose.translation + Quat::from_rotation_z(angle) * spoke;
+
+        for position in [-1.0, 0.0, 2.5] {
+            joint.position = position;
+            let link = joint.link_transform(&zero_pose, position).unwrap();
+            let (lower, upper) = limit_arc_endpoints(&link.into(), &joint, 1.0).unwrap();
+            assert!(lower.abs_diff_eq(at(joint.lower), 1e-5));
+            assert!(upper.abs_diff_eq(at(joint.upper), 1e-5));
+        }
+    }
+}
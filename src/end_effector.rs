@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::robot::{find_link_by_name, RobotLink};
+
+pub struct EndEffectorPlugin;
+
+impl Plugin for EndEffectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EeState>().add_systems(
+            PostUpdate,
+            track_end_effector
+                .after(TransformSystem::TransformPropagate)
+                .run_if(resource_exists::<EndEffector>),
+        );
+    }
+}
+
+/// Link whose world pose is tracked in `EeState`, as `<robot>/<link>` or a
+/// bare link name. With `print`, the pose is logged whenever it changes.
+#[derive(Resource)]
+pub struct EndEffector {
+    pub link: String,
+    pub print: bool,
+}
+
+/// World pose of the `EndEffector` link as of the last transform update.
+#[derive(Resource, Default, PartialEq)]
+pub struct EeState {
+    pub position: Vec3,
+    pub rotation: Quat,
+}
+
+fn track_end_effector(
+    end_effector: Res<EndEffector>,
+    links: Query<(Entity, &Name), With<RobotLink>>,
+    transforms: Query<&GlobalTransform>,
+    mut state: ResMut<EeState>,
+    mut reported_missing: Local<bool>,
+) {
+    let Some(transform) =
+        find_link_by_name(&links, &end_effector.link).and_then(|link| transforms.get(link).ok())
+    else {
+        if !*reported_missing {
+            error!("End effector link `{}` does not exist", end_effector.link);
+            *reported_missing = true;
+        }
+        return;
+    };
+
+    let (_, rotation, position) = transform.to_scale_rotation_translation();
+    let new_state = EeState { position, rotation };
+    if *state == new_state {
+        return;
+    }
+
+    if end_effector.print {
+        info!(
+            "End effector `{}` at {:.3}, rotation {:.3}",
+            end_effector.link, position, rotation
+        );
+    }
+    *state = new_state;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn follows_link_global_transform() {
+        let mut world = World::new();
+        world.init_resource::<EeState>();
+        world.insert_resource(EndEffector {
+            link: "gripper".to_string(),
+            print: false,
+        });
+        world.spawn((RobotLink, Name::new("arm/base"), GlobalTransform::IDENTITY));
+        let gripper = world
+            .spawn((
+                RobotLink,
+                Name::new("arm/gripper"),
+                GlobalTransform::from_xyz(0.1, 0.2, 0.3),
+            ))
+            .id();
+
+        world.run_system_once(track_end_effector);
+        assert_eq!(
+            world.resource::<EeState>().position,
+            Vec3::new(0.1, 0.2, 0.3)
+        );
+
+        let moved = Transform::from_xyz(-0.4, 0.5, 0.0).with_rotation(Quat::from_rotation_z(1.0));
+        *world.get_mut::<GlobalTransform>(gripper).unwrap() = moved.into();
+        world.run_system_once(track_end_effector);
+
+        let state = world.resource::<EeState>();
+        assert!(state.position.abs_diff_eq(moved.translation, 1e-6));
+        assert!(state.rotation.abs_diff_eq(moved.rotation, 1e-6));
+    }
+}
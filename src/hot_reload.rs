@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+
+use crate::{ReloadRobots, RobotSpawnConfigs};
+
+/// Respawns the robots whenever one of their URDF files changes on disk.
+/// Only built with the `hot_reload` feature.
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UrdfWatch>()
+            .add_systems(Update, watch_urdf_files);
+    }
+}
+
+const POLL_INTERVAL_SECS: f32 = 0.5;
+
+/// Last seen modification time of each URDF, polled every
+/// `POLL_INTERVAL_SECS`.
+#[derive(Resource)]
+struct UrdfWatch {
+    timer: Timer,
+    modified: HashMap<String, SystemTime>,
+}
+
+impl Default for UrdfWatch {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(POLL_INTERVAL_SECS, TimerMode::Repeating),
+            modified: HashMap::new(),
+        }
+    }
+}
+
+fn watch_urdf_files(
+    time: Res<Time>,
+    mut watch: ResMut<UrdfWatch>,
+    configs: Res<RobotSpawnConfigs>,
+    mut reload: EventWriter<ReloadRobots>,
+) {
+    if !watch.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut changed = false;
+    for config in &configs.0 {
+        let Ok(modified) = std::fs::metadata(&config.urdf_path).and_then(|m| m.modified()) else {
+            continue;
+        };
+
+        if let Some(previous) = watch.modified.insert(config.urdf_path.clone(), modified) {
+            if previous != modified {
+                info!(urdf_path = config.urdf_path, "URDF changed on disk");
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        reload.send(ReloadRobots);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::RobotSpawnConfig;
+
+    fn poll(world: &mut World) -> usize {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(POLL_INTERVAL_SECS));
+        world.run_system_once(watch_urdf_files);
+        world.resource_mut::<Events<ReloadRobots>>().drain().count()
+    }
+
+    #[test]
+    fn reloads_when_urdf_changes() {
+        let path = std::env::temp_dir().join(format!("hot-reload-{}.urdf", std::process::id()));
+        let file = File::create(&path).unwrap();
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<UrdfWatch>();
+        world.init_resource::<Events<ReloadRobots>>();
+        world.insert_resource(RobotSpawnConfigs(vec![RobotSpawnConfig {
+            urdf_path: path.to_string_lossy().into_owned(),
+            ..default()
+        }]));
+
+        let first = poll(&mut world);
+        let unchanged = poll(&mut world);
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        let changed = poll(&mut world);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((first, unchanged, changed), (0, 0, 1));
+    }
+}
@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+use urdf_rs::JointType;
+
+use crate::end_effector::EndEffector;
+use crate::robot::{find_link_by_name, InitialPose, RobotJoint, RobotLink};
+
+pub struct IkPlugin;
+
+impl Plugin for IkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            solve_ik.run_if(resource_exists::<IkTarget>.and_then(resource_exists::<EndEffector>)),
+        );
+    }
+}
+
+/// World-space point the `EndEffector` link should reach. Each frame runs
+/// `iterations` sweeps of cyclic coordinate descent over the joints between
+/// the robot root and the end effector, writing the result to their
+/// `RobotJoint::position` within the joint limits.
+#[derive(Resource)]
+pub struct IkTarget {
+    pub position: Vec3,
+    pub iterations: usize,
+    /// Distance in metres at which the target counts as reached.
+    pub tolerance: f32,
+}
+
+impl IkTarget {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            iterations: 10,
+            tolerance: 1e-3,
+        }
+    }
+}
+
+/// One link between the robot root and the end effector, top first.
+struct ChainLink {
+    entity: Entity,
+    /// The link's transform relative to its parent when `joint` is `None`,
+    /// otherwise its `InitialPose`.
+    transform: Transform,
+    joint: Option<RobotJoint>,
+}
+
+/// World transform of every link in `chain`, given the transform of the
+/// entity above the top link.
+fn forward_kinematics(base: Transform, chain: &[ChainLink]) -> Vec<Transform> {
+    let mut frames = Vec::with_capacity(chain.len());
+    let mut frame = base;
+    for link in chain {
+        let local = link
+            .joint
+            .as_ref()
+            .and_then(|joint| joint.link_transform(&link.transform, joint.position))
+            .unwrap_or(link.transform);
+        frame = frame.mul_transform(local);
+        frames.push(frame);
+    }
+    frames
+}
+
+/// One cyclic coordinate descent sweep from the end effector back to the
+/// root: each joint in turn moves as far as its limits allow towards
+/// putting the end of the chain on `target`.
+fn ccd_sweep(base: Transform, chain: &mut [ChainLink], target: Vec3) {
+    for i in (0..chain.len()).rev() {
+        if chain[i].joint.is_none() {
+            continue;
+        }
+
+        let frames = forward_kinematics(base, chain);
+        let frame = frames[i];
+        let end = frames[chain.len() - 1].translation;
+        let Some(joint) = &mut chain[i].joint else {
+            continue;
+        };
+        let axis = (frame.rotation * joint.axis.normalize()).normalize();
+
+        let delta = match joint.joint_type {
+            JointType::Prismatic => (target - end).dot(axis),
+            _ => {
+                let to_end = (end - frame.translation).reject_from_normalized(axis);
+                let to_target = (target - frame.translation).reject_from_normalized(axis);
+                if to_end.length_squared() < f32::EPSILON
+                    || to_target.length_squared() < f32::EPSILON
+                {
+                    continue;
+                }
+                let angle = to_end.angle_between(to_target);
+                angle * to_end.cross(to_target).dot(axis).signum()
+            }
+        };
+        joint.position = joint.clamp_position(joint.position + delta);
+    }
+}
+
+/// Runs up to `target.iterations` sweeps, stopping once the end of the chain
+/// is within `target.tolerance` of the target.
+fn solve_chain(base: Transform, chain: &mut [ChainLink], target: &IkTarget) {
+    for _ in 0..target.iterations {
+        let frames = forward_kinematics(base, chain);
+        if frames[chain.len() - 1]
+            .translation
+            .distance(target.position)
+            < target.tolerance
+        {
+            break;
+        }
+        ccd_sweep(base, chain, target.position);
+    }
+}
+
+fn solve_ik(
+    target: Res<IkTarget>,
+    end_effector: Res<EndEffector>,
+    names: Query<(Entity, &Name), With<RobotLink>>,
+    mut links: Query<(
+        &InitialPose,
+        &Transform,
+        Option<&Parent>,
+        Option<&mut RobotJoint>,
+    )>,
+    global_transforms: Query<&GlobalTransform>,
+) {
+    let Some(end) = find_link_by_name(&names, &end_effector.link) else {
+        return;
+    };
+
+    let mut chain = Vec::new();
+    let mut entity = end;
+    let base = loop {
+        let Ok((initial_pose, transform, parent, joint)) = links.get(entity) else {
+            return;
+        };
+        let joint = joint.filter(|joint| joint.is_movable() && joint.axis != Vec3::ZERO);
+        chain.push(ChainLink {
+            entity,
+            transform: if joint.is_some() {
+                initial_pose.0
+            } else {
+                *transform
+            },
+            joint: joint.cloned(),
+        });
+
+        let Some(parent) = parent else {
+            return;
+        };
+        // The robot root is the first ancestor that isn't a link.
+        if names.contains(parent.get()) {
+            entity = parent.get();
+        } else {
+            let Ok(base) = global_transforms.get(parent.get()) else {
+                return;
+            };
+            break base.compute_transform();
+        }
+    };
+    chain.reverse();
+
+    solve_chain(base, &mut chain, &target);
+
+    for link in &chain {
+        let (Some(solved), Ok((_, _, _, Some(mut joint)))) =
+            (&link.joint, links.get_mut(link.entity))
+        else {
+            continue;
+        };
+        if joint.position != solved.position {
+            joint.position = solved.position;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revolute(name: &str, lower: f32, upper: f32) -> RobotJoint {
+        RobotJoint {
+            name: name.to_string(),
+            joint_type: JointType::Revolute,
+            axis: Vec3::Z,
+            lower,
+            upper,
+            position: 0.0,
+        }
+    }
+
+    /// Two 1 m links turning about Z, with the end effector at the tip of
+    /// the second one, stretched out along X at zero.
+    fn two_link_chain(lower: f32, upper: f32) -> Vec<ChainLink> {
+        vec![
+            ChainLink {
+                entity: Entity::from_raw(0),
+                transform: Transform::IDENTITY,
+                joint: Some(revolute("shoulder", lower, upper)),
+            },
+            ChainLink {
+                entity: Entity::from_raw(1),
+                transform: Transform::from_xyz(1.0, 0.0, 0.0),
+                joint: Some(revolute("elbow", lower, upper)),
+            },
+            ChainLink {
+                entity: Entity::from_raw(2),
+                transform: Transform::from_xyz(1.0, 0.0, 0.0),
+                joint: None,
+            },
+        ]
+    }
+
+    fn end_position(chain: &[ChainLink]) -> Vec3 {
+        forward_kinematics(Transform::IDENTITY, chain)[chain.len() - 1].translation
+    }
+
+    #[test]
+    fn reaches_target_within_tolerance() {
+        let mut chain = two_link_chain(-3.0, 3.0);
+        let target = IkTarget {
+            iterations: 100,
+            ..IkTarget::new(Vec3::new(1.0, 1.2, 0.0))
+        };
+
+        solve_chain(Transform::IDENTITY, &mut chain, &target);
+
+        let distance = end_position(&chain).distance(target.position);
+        assert!(distance < target.tolerance, "end is {distance} m away");
+    }
+
+    #[test]
+    fn unreachable_target_keeps_joints_within_limits() {
+        let mut chain = two_link_chain(-0.5, 0.5);
+        let target = IkTarget::new(Vec3::new(-5.0, -1.0, 0.0));
+
+        solve_chain(Transform::IDENTITY, &mut chain, &target);
+
+        for joint in chain.iter().filter_map(|link| link.joint.as_ref()) {
+            assert!(
+                (joint.lower..=joint.upper).contains(&joint.position),
+                "{} is at {}",
+                joint.name,
+                joint.position
+            );
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use bevy::math::{DMat3, DVec3, EulerRot};
+use urdf_rs::Geometry;
+
+#[derive(Debug, PartialEq)]
+pub enum InertiaError {
+    NonPositiveDiagonal,
+    NotPositiveDefinite,
+}
+
+impl std::fmt::Display for InertiaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InertiaError::NonPositiveDiagonal => write!(f, "diagonal terms must be positive"),
+            InertiaError::NotPositiveDefinite => write!(f, "tensor is not positive definite"),
+        }
+    }
+}
+
+impl std::error::Error for InertiaError {}
+
+/// Checks that a link's inertia tensor is physically plausible. Links without
+/// an `<inertial>` element (zero mass and tensor) are accepted as visual-only.
+pub fn validate_inertia(link: &urdf_rs::Link) -> Result<(), InertiaError> {
+    let inertial = &link.inertial;
+    let tensor = inertia_matrix(&inertial.inertia);
+
+    if inertial.mass.value == 0.0 && tensor == DMat3::ZERO {
+        return Ok(());
+    }
+
+    if tensor.x_axis.x <= 0.0 || tensor.y_axis.y <= 0.0 || tensor.z_axis.z <= 0.0 {
+        return Err(InertiaError::NonPositiveDiagonal);
+    }
+
+    // Sylvester's criterion: every leading principal minor must be positive.
+    let minor_2 = tensor.x_axis.x * tensor.y_axis.y - tensor.x_axis.y * tensor.y_axis.x;
+    if minor_2 <= 0.0 || tensor.determinant() <= 0.0 {
+        return Err(InertiaError::NotPositiveDefinite);
+    }
+
+    Ok(())
+}
+
+/// What spawning does with a link whose tensor fails [`validate_inertia`].
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum InvalidInertia {
+    /// Log a warning and keep the tensor.
+    #[default]
+    Warn,
+    /// Replace the tensor with [`fix_inertia`] where possible.
+    Fix,
+}
+
+/// Replaces the link's inertia tensor with that of a uniform box of the
+/// link's mass filling its [`primitive_bounds`], aligned with the link frame.
+/// The center of mass stays where it is. Returns `false`, leaving the link
+/// untouched, when the link has no mass or no primitive geometry.
+pub fn fix_inertia(link: &mut urdf_rs::Link) -> bool {
+    let mass = link.inertial.mass.value;
+    let Some((min, max)) = primitive_bounds(link) else {
+        return false;
+    };
+    if mass <= 0.0 {
+        return false;
+    }
+
+    let moments = box_inertia(mass, max - min);
+    link.inertial.inertia = urdf_rs::Inertia {
+        ixx: moments.x,
+        iyy: moments.y,
+        izz: moments.z,
+        ..Default::default()
+    };
+    link.inertial.origin.rpy = urdf_rs::Vec3([0.0; 3]);
+    true
+}
+
+/// Principal moments of a uniform box of `mass` with edge lengths `size`.
+pub fn box_inertia(mass: f64, size: DVec3) -> DVec3 {
+    let squared = size * size;
+    DVec3::new(
+        squared.y + squared.z,
+        squared.x + squared.z,
+        squared.x + squared.y,
+    ) * (mass / 12.0)
+}
+
+/// Box around a link's primitive collision shapes in the link frame, or its
+/// visual shapes when it has no collision shapes. Meshes are skipped, as
+/// their size isn't known until they load.
+pub fn primitive_bounds(link: &urdf_rs::Link) -> Option<(DVec3, DVec3)> {
+    let collisions = link
+        .collision
+        .iter()
+        .map(|collision| (&collision.geometry, &collision.origin));
+    let visuals = link
+        .visual
+        .iter()
+        .map(|visual| (&visual.geometry, &visual.origin));
+    let shapes: Vec<_> = if link.collision.is_empty() {
+        visuals.collect()
+    } else {
+        collisions.collect()
+    };
+
+    let mut bounds: Option<(DVec3, DVec3)> = None;
+    for (geometry, origin) in shapes {
+        let half_extents = match geometry {
+            Geometry::Box { size } => DVec3::from_array(size.0) / 2.0,
+            Geometry::Cylinder { radius, length } => DVec3::new(*radius, *radius, length / 2.0),
+            Geometry::Capsule { radius, length } => {
+                DVec3::new(*radius, *radius, length / 2.0 + radius)
+            }
+            Geometry::Sphere { radius } => DVec3::splat(*radius),
+            Geometry::Mesh { .. } => continue,
+        };
+
+        // Same convention as the transforms links are spawned with.
+        let rotation =
+            DMat3::from_euler(EulerRot::XYZ, origin.rpy[0], origin.rpy[1], origin.rpy[2]);
+        let center = DVec3::from_array(origin.xyz.0);
+        for corner in 0..8 {
+            let sign = DVec3::new(
+                if corner & 1 == 0 { -1.0 } else { 1.0 },
+                if corner & 2 == 0 { -1.0 } else { 1.0 },
+                if corner & 4 == 0 { -1.0 } else { 1.0 },
+            );
+            let point = center + rotation * (sign * half_extents);
+            bounds = Some(match bounds {
+                Some((min, max)) => (min.min(point), max.max(point)),
+                None => (point, point),
+            });
+        }
+    }
+    bounds
+}
+
+pub fn inertia_matrix(inertia: &urdf_rs::Inertia) -> DMat3 {
+    DMat3::from_cols(
+        DVec3::new(inertia.ixx, inertia.ixy, inertia.ixz),
+        DVec3::new(inertia.ixy, inertia.iyy, inertia.iyz),
+        DVec3::new(inertia.ixz, inertia.iyz, inertia.izz),
+    )
+}
+
+/// Principal moments and axes of a symmetric inertia tensor, found with
+/// cyclic Jacobi rotations. Axis `i` of the returned matrix belongs to moment
+/// `i`, and the axes form a right-handed rotation.
+pub fn principal_axes(tensor: DMat3) -> (DVec3, DMat3) {
+    let mut a = tensor.to_cols_array_2d();
+    let mut v = DMat3::IDENTITY.to_cols_array_2d();
+
+    for _ in 0..32 {
+        let off_diagonal = a[0][1].powi(2) + a[0][2].powi(2) + a[1][2].powi(2);
+        if off_diagonal < 1e-24 {
+            break;
+        }
+
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q] == 0.0 {
+                continue;
+            }
+
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+
+            for row in &mut a {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = std::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = std::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+            // `v` is stored column-major, so v[p] is eigenvector p.
+            let (axis_p, axis_q) = (v[p], v[q]);
+            v[p] = std::array::from_fn(|k| c * axis_p[k] - s * axis_q[k]);
+            v[q] = std::array::from_fn(|k| s * axis_p[k] + c * axis_q[k]);
+        }
+    }
+
+    let mut axes = DMat3::from_cols_array_2d(&v);
+    if axes.determinant() < 0.0 {
+        axes.z_axis = -axes.z_axis;
+    }
+    (DVec3::new(a[0][0], a[1][1], a[2][2]), axes)
+}
+
+/// Semi-axes of the uniform solid ellipsoid with `mass` and the given
+/// principal `moments`, which has the same inertia as the link.
+pub fn equivalent_ellipsoid(mass: f64, moments: DVec3) -> DVec3 {
+    let scale = 5.0 / (2.0 * mass);
+    (DVec3::new(
+        moments.y + moments.z - moments.x,
+        moments.x + moments.z - moments.y,
+        moments.x + moments.y - moments.z,
+    ) * scale)
+        .max(DVec3::ZERO)
+        .powf(0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: DVec3, expected: DVec3) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-9),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    /// A 2 kg link with a 0.2 x 0.4 x 0.6 m box collision shape.
+    fn box_link(ixx: f64, iyy: f64, izz: f64, ixy: f64) -> urdf_rs::Link {
+        let urdf = format!(
+            r#"<robot name="test">
+                <link name="body">
+                    <inertial>
+                        <mass value="2"/>
+                        <inertia ixx="{ixx}" ixy="{ixy}" ixz="0" iyy="{iyy}" iyz="0" izz="{izz}"/>
+                    </inertial>
+                    <collision>
+                        <origin xyz="0 0 0.1" rpy="0 0 0"/>
+                        <geometry><box size="0.2 0.4 0.6"/></geometry>
+                    </collision>
+                </link>
+            </robot>"#
+        );
+        urdf_rs::read_from_string(&urdf).unwrap().links.remove(0)
+    }
+
+    #[test]
+    fn rejects_non_positive_diagonal() {
+        let link = box_link(0.1, 0.0, 0.1, 0.0);
+        assert_eq!(
+            validate_inertia(&link),
+            Err(InertiaError::NonPositiveDiagonal)
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_definite() {
+        let link = box_link(0.1, 0.1, 0.1, 0.2);
+        assert_eq!(
+            validate_inertia(&link),
+            Err(InertiaError::NotPositiveDefinite)
+        );
+    }
+
+    #[test]
+    fn fix_replaces_invalid_tensor_with_box_inertia() {
+        let mut link = box_link(0.1, 0.1, 0.1, 0.2);
+
+        assert!(fix_inertia(&mut link));
+
+        assert_eq!(validate_inertia(&link), Ok(()));
+        let tensor = inertia_matrix(&link.inertial.inertia);
+        let expected = box_inertia(2.0, DVec3::new(0.2, 0.4, 0.6));
+        assert!(tensor.abs_diff_eq(DMat3::from_diagonal(expected), 1e-12));
+        assert_close(expected, DVec3::new(0.52, 0.40, 0.20) / 6.0);
+    }
+
+    #[test]
+    fn fix_needs_primitive_geometry() {
+        let mut link = box_link(0.1, 0.1, 0.1, 0.2);
+        link.collision.clear();
+
+        assert!(!fix_inertia(&mut link));
+        assert_eq!(
+            validate_inertia(&link),
+            Err(InertiaError::NotPositiveDefinite)
+        );
+    }
+
+    #[test]
+    fn diagonal_tensor_is_already_principal() {
+        let (moments, axes) = principal_axes(DMat3::from_diagonal(DVec3::new(1.0, 2.0, 3.0)));
+
+        assert_close(moments, DVec3::new(1.0, 2.0, 3.0));
+        assert!(axes.abs_diff_eq(DMat3::IDENTITY, 1e-9));
+    }
+
+    #[test]
+    fn rotated_tensor_recovers_moments_and_axes() {
+        let rotation = DMat3::from_euler(bevy::math::EulerRot::XYZ, 0.3, -0.7, 1.1);
+        let tensor =
+            rotation * DMat3::from_diagonal(DVec3::new(1.0, 2.0, 3.0)) * rotation.transpose();
+
+        let (moments, axes) = principal_axes(tensor);
+
+        let mut sorted = moments.to_array();
+        sorted.sort_by(f64::total_cmp);
+        assert_close(DVec3::from_array(sorted), DVec3::new(1.0, 2.0, 3.0));
+        for i in 0..3 {
+            let axis = axes.col(i);
+            assert_close(tensor * axis, axis * moments[i]);
+        }
+        assert!((axes.determinant() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn uniform_ellipsoid_semi_axes_round_trip() {
+        let (mass, semi_axes) = (2.0, DVec3::new(0.1, 0.2, 0.3));
+        let squared = semi_axes * semi_axes;
+        let moments = DVec3::new(
+            squared.y + squared.z,
+            squared.x + squared.z,
+            squared.x + squared.y,
+        ) * (mass / 5.0);
+
+        assert_close(equivalent_ellipsoid(mass, moments), semi_axes);
+    }
+}
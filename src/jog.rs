@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+use urdf_rs::JointType;
+
+use crate::robot::{LinkColorOverrides, RobotJoint};
+
+pub struct JointJogPlugin;
+
+impl Plugin for JointJogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveJoint>().add_systems(
+            Update,
+            (
+                select_active_joint,
+                jog_active_joint,
+                highlight_active_joint,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The joint Up/Down jogs. Tab cycles it through every movable joint, then
+/// back to none.
+#[derive(Resource, Default)]
+pub struct ActiveJoint(pub Option<Entity>);
+
+/// Radians per second for revolute joints.
+const JOG_ANGULAR_SPEED: f32 = 1.0;
+/// Metres per second for prismatic joints.
+const JOG_LINEAR_SPEED: f32 = 0.05;
+const ACTIVE_JOINT_TINT: Color = Color::srgb(1.0, 0.6, 0.1);
+
+fn select_active_joint(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut active: ResMut<ActiveJoint>,
+    joints: Query<(Entity, &RobotJoint)>,
+) {
+    if !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+
+    let mut movable: Vec<_> = joints
+        .iter()
+        .filter(|(_, joint)| joint.is_movable())
+        .map(|(entity, _)| entity)
+        .collect();
+    movable.sort();
+
+    active.0 = match active
+        .0
+        .and_then(|current| movable.iter().position(|&entity| entity == current))
+    {
+        Some(i) => movable.get(i + 1).copied(),
+        None => movable.first().copied(),
+    };
+
+    match active.0.and_then(|entity| joints.get(entity).ok()) {
+        Some((_, joint)) => info!("Active joint: {}", joint.name),
+        None => info!("No active joint"),
+    }
+}
+
+fn jog_active_joint(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    active: Res<ActiveJoint>,
+    mut joints: Query<&mut RobotJoint>,
+) {
+    let Some(mut joint) = active.0.and_then(|entity| joints.get_mut(entity).ok()) else {
+        return;
+    };
+
+    let mut direction = 0.0;
+    if keys.pressed(KeyCode::ArrowUp) {
+        direction += 1.0;
+    }
+    if keys.pressed(KeyCode::ArrowDown) {
+        direction -= 1.0;
+    }
+    if direction == 0.0 {
+        return;
+    }
+
+    let speed = match joint.joint_type {
+        JointType::Prismatic => JOG_LINEAR_SPEED,
+        _ => JOG_ANGULAR_SPEED,
+    };
+    let position = joint.position + direction * speed * time.delta_seconds();
+    joint.position = joint.clamp_position(position);
+}
+
+/// Tints the active joint's link, restoring whatever color override it had
+/// once another joint is selected.
+fn highlight_active_joint(
+    active: Res<ActiveJoint>,
+    names: Query<&Name>,
+    mut color_overrides: ResMut<LinkColorOverrides>,
+    mut highlighted: Local<Option<(String, Option<Color>)>>,
+) {
+    if !active.is_changed() {
+        return;
+    }
+
+    if let Some((link, previous)) = highlighted.take() {
+        match previous {
+            Some(color) => color_overrides.0.insert(link, color),
+            None => color_overrides.0.remove(&link),
+        };
+    }
+
+    // Color overrides are keyed by the bare link name, without the robot.
+    let link_name = active
+        .0
+        .and_then(|entity| names.get(entity).ok())
+        .and_then(|name| name.as_str().rsplit_once('/'))
+        .map(|(_, link)| link.to_string());
+    if let Some(link) = link_name {
+        let previous = color_overrides.0.insert(link.clone(), ACTIVE_JOINT_TINT);
+        *highlighted = Some((link, previous));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn joint(name: &str, joint_type: JointType) -> RobotJoint {
+        RobotJoint {
+            name: name.to_string(),
+            joint_type,
+            axis: Vec3::Y,
+            lower: -0.5,
+            upper: 0.5,
+            position: 0.0,
+        }
+    }
+
+    /// Presses `key` afresh, so it counts as just pressed.
+    fn tap(world: &mut World, key: KeyCode) {
+        let mut keys = world.resource_mut::<ButtonInput<KeyCode>>();
+        keys.reset_all();
+        keys.press(key);
+    }
+
+    #[test]
+    fn tab_cycles_through_movable_joints() {
+        let mut world = World::new();
+        world.init_resource::<ActiveJoint>();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        let shoulder = world.spawn(joint("arm/shoulder", JointType::Revolute)).id();
+        world.spawn(joint("arm/mount", JointType::Fixed));
+        let slider = world.spawn(joint("arm/slider", JointType::Prismatic)).id();
+
+        let mut selected = Vec::new();
+        for _ in 0..3 {
+            tap(&mut world, KeyCode::Tab);
+            world.run_system_once(select_active_joint);
+            selected.push(world.resource::<ActiveJoint>().0);
+        }
+        assert_eq!(selected, [Some(shoulder), Some(slider), None]);
+    }
+
+    #[test]
+    fn arrows_jog_within_limits() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        let shoulder = world.spawn(joint("arm/shoulder", JointType::Revolute)).id();
+        world.insert_resource(ActiveJoint(Some(shoulder)));
+        let jog = |world: &mut World, key, millis| {
+            tap(world, key);
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(millis));
+            world.run_system_once(jog_active_joint);
+            world.get::<RobotJoint>(shoulder).unwrap().position
+        };
+
+        assert!((jog(&mut world, KeyCode::ArrowUp, 200) - 0.2).abs() < 1e-6);
+        assert_eq!(jog(&mut world, KeyCode::ArrowUp, 1000), 0.5);
+        assert!((jog(&mut world, KeyCode::ArrowDown, 300) - 0.2).abs() < 1e-6);
+        assert_eq!(jog(&mut world, KeyCode::ArrowDown, 2000), -0.5);
+    }
+}
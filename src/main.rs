@@ -1,6 +1,7 @@
+use bevy::input::InputPlugin;
+use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use bevy_fps_counter::FpsCounterPlugin;
-use urdf_rs::{Geometry, Pose};
 
 mod world;
 use world::WorldPlugin;
@@ -8,182 +9,420 @@ use world::WorldPlugin;
 mod camera;
 use camera::CameraPlugin;
 
-fn main() {
-    App::new()
-        .add_plugins((
-            DefaultPlugins,
-            bevy_stl::StlPlugin,
-            FpsCounterPlugin,
-            WorldPlugin,
-            CameraPlugin,
-        ))
-        .add_systems(Startup, (spawn_robots, process_urdf_visuals).chain())
-        .run();
-}
+mod cli;
+use cli::{Cli, CliError, USAGE};
+
+#[cfg(feature = "control_server")]
+mod control_server;
+
+mod debug;
+use debug::DebugPlugin;
+
+mod end_effector;
+use end_effector::EndEffectorPlugin;
+
+mod ik;
+use ik::IkPlugin;
+
+mod inertia;
+use inertia::InvalidInertia;
+
+mod jog;
+use jog::JointJogPlugin;
+
+mod measure;
+use measure::MeasurePlugin;
+
+mod recorder;
+use recorder::JointRecorderPlugin;
+
+mod robot;
+use robot::{
+    despawn_robot, spawn_robot_from_path, up_axis_correction, MassOverrides, RobotHandle,
+    RobotPlugin, SpawnIndex, UpAxis,
+};
+
+mod segmentation;
+use segmentation::SegmentationPlugin;
+
+mod snapshot;
+use snapshot::SnapshotPlugin;
+
+mod teach;
+use teach::TeachPlugin;
+
+mod teleop;
+use teleop::BaseTeleopPlugin;
+
+mod trajectory;
+use trajectory::JointTrajectoryPlugin;
+
+#[cfg(feature = "hot_reload")]
+mod hot_reload;
+
+fn main() -> AppExit {
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(CliError::Help) => {
+            println!("{USAGE}");
+            return AppExit::Success;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return AppExit::error();
+        }
+    };
+
+    let mut app = App::new();
 
-#[derive(Component)]
-struct Robot;
+    match cli.mode {
+        SimMode::Windowed => {
+            app.add_plugins((
+                DefaultPlugins,
+                FpsCounterPlugin,
+                CameraPlugin,
+                DebugPlugin,
+                BaseTeleopPlugin,
+                JointJogPlugin,
+                MeasurePlugin,
+                SegmentationPlugin,
+                SnapshotPlugin,
+                TeachPlugin,
+            ));
+        }
+        SimMode::Headless { steps } => {
+            app.add_plugins((
+                MinimalPlugins,
+                LogPlugin::default(),
+                AssetPlugin::default(),
+                TransformPlugin,
+                HierarchyPlugin,
+                InputPlugin,
+            ))
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .init_asset::<Image>()
+            .insert_resource(HeadlessSteps(steps))
+            .add_systems(Update, exit_after_headless_steps);
+        }
+    }
 
-#[derive(Component)]
-struct RobotPart;
+    app.add_plugins((
+        bevy_stl::StlPlugin,
+        WorldPlugin,
+        RobotPlugin,
+        EndEffectorPlugin,
+        IkPlugin,
+        JointRecorderPlugin,
+        JointTrajectoryPlugin,
+    ));
+
+    app.insert_resource(cli.camera)
+        .insert_resource(cli.quality)
+        .insert_resource(cli.floor)
+        .insert_resource(cli.environment)
+        .insert_resource(cli.lighting)
+        .insert_resource(cli.packages)
+        .insert_resource(cli.hidden_links)
+        .insert_resource(cli.mass_overrides)
+        .insert_resource(cli.spawn_check)
+        .insert_resource(cli.link_materials)
+        .insert_resource(cli.segmentation)
+        .insert_resource(cli.recorder)
+        .insert_resource(cli.trajectory);
+    if let Some(end_effector) = cli.end_effector {
+        app.insert_resource(end_effector);
+    }
+    if let Some(ik_target) = cli.ik_target {
+        app.insert_resource(ik_target);
+    }
 
-#[derive(Component)]
-struct UrdfVisual {
-    geometry: Geometry,
-    material: Option<urdf_rs::Material>,
-    origin: Pose,
+    #[cfg(feature = "hot_reload")]
+    app.add_plugins(hot_reload::HotReloadPlugin);
+    #[cfg(feature = "control_server")]
+    app.add_plugins(control_server::ControlServerPlugin);
+
+    app.insert_resource(cli.robots)
+        .init_resource::<SpawnedRobots>()
+        .add_event::<ReloadRobots>()
+        .add_systems(Startup, spawn_robots)
+        .add_systems(Update, reload_robots)
+        .run()
 }
 
-fn spawn_robots(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+/// `Headless` runs without a window or renderer for a fixed number of
+/// updates, then exits with an error code if any transform went non-finite.
+/// Selected with `--headless`.
+enum SimMode {
+    Windowed,
+    Headless { steps: u32 },
+}
+
+#[derive(Resource)]
+struct HeadlessSteps(u32);
+
+fn exit_after_headless_steps(
+    mut step: Local<u32>,
+    steps: Res<HeadlessSteps>,
+    transforms: Query<(Entity, &GlobalTransform)>,
+    mut exit: EventWriter<AppExit>,
 ) {
-    spawn_robot(
-        &mut commands,
-        &asset_server,
-        &mut materials,
-        Transform::from_xyz(0.0, 0.3, 0.0),
-    );
+    *step += 1;
+    if *step < steps.0 {
+        return;
+    }
+
+    let mut invalid = 0;
+    for (entity, transform) in transforms.iter() {
+        if !transform.affine().is_finite() {
+            error!(
+                "{entity:?} has a non-finite transform after {} steps",
+                steps.0
+            );
+            invalid += 1;
+        }
+    }
+
+    if invalid == 0 {
+        info!("Headless run finished {} steps", steps.0);
+        exit.send(AppExit::Success);
+    } else {
+        exit.send(AppExit::error());
+    }
 }
 
-fn spawn_robot(
-    commands: &mut Commands,
-    _asset_server: &Res<AssetServer>,
-    _materials: &mut ResMut<Assets<StandardMaterial>>,
+/// Robots `spawn_robots` lays out at startup, one entry per URDF.
+#[derive(Resource)]
+struct RobotSpawnConfigs(Vec<RobotSpawnConfig>);
+
+impl Default for RobotSpawnConfigs {
+    fn default() -> Self {
+        Self(vec![RobotSpawnConfig::default()])
+    }
+}
+
+/// Spawns `count` copies of one URDF. Copy `i` is placed at `base_transform`
+/// offset by `i * spacing`, turned so the URDF's `up_axis` points up.
+/// `invalid_inertia` decides what happens to links with a bad inertia tensor.
+struct RobotSpawnConfig {
+    urdf_path: String,
+    up_axis: UpAxis,
     base_transform: Transform,
+    count: usize,
+    spacing: Vec3,
+    invalid_inertia: InvalidInertia,
+}
+
+impl Default for RobotSpawnConfig {
+    fn default() -> Self {
+        Self {
+            urdf_path: "sample_description/urdf/low_cost_robot.urdf".to_string(),
+            up_axis: UpAxis::Z,
+            base_transform: Transform::from_xyz(0.0, 0.3, 0.0),
+            count: 1,
+            spacing: Vec3::new(0.5, 0.0, 0.0),
+            invalid_inertia: InvalidInertia::default(),
+        }
+    }
+}
+
+/// Robots currently in the world, replaced when F5 reloads them.
+#[derive(Resource, Default)]
+struct SpawnedRobots(Vec<RobotHandle>);
+
+/// Despawns every robot and spawns them again from `RobotSpawnConfigs`.
+#[derive(Event)]
+struct ReloadRobots;
+
+fn spawn_robots(
+    mut commands: Commands,
+    configs: Res<RobotSpawnConfigs>,
+    mass_overrides: Res<MassOverrides>,
+    mut spawned: ResMut<SpawnedRobots>,
 ) {
-    let urdf_path = "sample_description/urdf/low_cost_robot.urdf";
-    let robot = urdf_rs::read_file(urdf_path).expect("Failed to read URDF file");
-
-    commands
-        .spawn((
-            Robot,
-            TransformBundle::from_transform(base_transform),
-            VisibilityBundle::default(),
-        ))
-        .with_children(|parent| {
-            for link in &robot.links {
-                for visual in &link.visual {
-                    parent.spawn((
-                        RobotPart,
-                        UrdfVisual {
-                            geometry: visual.geometry.clone(),
-                            material: visual.material.clone(),
-                            origin: visual.origin.clone(),
-                        },
-                        TransformBundle::default(),
-                        VisibilityBundle::default(),
-                    ));
-                }
-            }
-        });
+    spawned.0 = spawn_configured_robots(&mut commands, &configs, &mass_overrides);
 }
 
-fn process_urdf_visuals(
+fn reload_robots(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    query: Query<(Entity, &UrdfVisual), Added<UrdfVisual>>,
-    asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut reload_requests: EventReader<ReloadRobots>,
+    configs: Res<RobotSpawnConfigs>,
+    mass_overrides: Res<MassOverrides>,
+    mut spawned: ResMut<SpawnedRobots>,
 ) {
-    for (entity, urdf_visual) in query.iter() {
-        let (mesh_handle, material_handle) = match &urdf_visual.geometry {
-            Geometry::Mesh { filename, .. } => {
-                let mesh_handle = asset_server.load(filename);
-                let material_handle = create_material(&urdf_visual.material, &mut materials);
-                (mesh_handle, material_handle)
-            }
-            Geometry::Box { size } => {
-                let mesh = Mesh::from(Cuboid::new(size[0] as f32, size[1] as f32, size[2] as f32));
-                let mesh_handle = meshes.add(mesh);
-                let material_handle = create_material(&urdf_visual.material, &mut materials);
-                (mesh_handle, material_handle)
-            }
-            Geometry::Cylinder { radius, length } => {
-                let mesh = Mesh::from(Cylinder {
-                    radius: *radius as f32,
-                    half_height: *length as f32,
-                });
-                let mesh_handle = meshes.add(mesh);
-                let material_handle = create_material(&urdf_visual.material, &mut materials);
-                (mesh_handle, material_handle)
-            }
-            Geometry::Sphere { radius } => {
-                let mesh = Mesh::from(Sphere {
-                    radius: *radius as f32,
-                });
-                let mesh_handle = meshes.add(mesh);
-                let material_handle = create_material(&urdf_visual.material, &mut materials);
-                (mesh_handle, material_handle)
-            }
-            _ => {
-                warn!("Unsupported geometry type");
-                continue;
-            }
-        };
+    let requested = reload_requests.read().count() > 0;
+    if !requested && !keys.just_pressed(KeyCode::F5) {
+        return;
+    }
 
-        let transform = urdf_to_transform(&urdf_visual.origin, &urdf_visual.geometry);
+    for robot in spawned.0.drain(..) {
+        despawn_robot(&mut commands, robot);
+    }
+    spawned.0 = spawn_configured_robots(&mut commands, &configs, &mass_overrides);
+}
+
+fn spawn_configured_robots(
+    commands: &mut Commands,
+    configs: &RobotSpawnConfigs,
+    mass_overrides: &MassOverrides,
+) -> Vec<RobotHandle> {
+    let mut spawned = Vec::new();
+    for config in &configs.0 {
+        let urdf_path = config.urdf_path.as_str();
 
-        commands.entity(entity).insert(PbrBundle {
-            mesh: mesh_handle,
-            material: material_handle,
-            transform,
-            ..Default::default()
-        });
+        for i in 0..config.count {
+            let mut base_transform = config.base_transform;
+            base_transform.translation += config.spacing * i as f32;
+            base_transform.rotation *= up_axis_correction(config.up_axis);
+
+            let spawned_robot = spawn_robot_from_path(
+                commands,
+                urdf_path,
+                base_transform,
+                config.invalid_inertia,
+                mass_overrides,
+            );
+            match spawned_robot {
+                Ok(robot) => {
+                    commands
+                        .entity(robot.root)
+                        .insert(SpawnIndex(spawned.len()));
+                    info!(
+                        urdf_path,
+                        "Spawned robot {:?} with {} links",
+                        robot.root,
+                        robot.links.len()
+                    );
+                    spawned.push(robot);
+                }
+                Err(err) => error!(urdf_path, "Failed to spawn robot: {err}"),
+            }
+        }
     }
+    spawned
 }
 
-fn create_material(
-    urdf_material: &Option<urdf_rs::Material>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-) -> Handle<StandardMaterial> {
-    let color = if let Some(material) = urdf_material {
-        if let Some(urdf_color) = &material.color {
-            Color::srgba(
-                urdf_color.rgba[0] as f32,
-                urdf_color.rgba[1] as f32,
-                urdf_color.rgba[2] as f32,
-                urdf_color.rgba[3] as f32,
-            )
-        } else {
-            Color::srgba(0.8, 0.8, 0.8, 1.0)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robot::Robot;
+
+    /// The headless plugin set, without logging, running the robots in
+    /// `configs` for `steps` updates.
+    fn headless_app(configs: RobotSpawnConfigs, steps: u32) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        .insert_resource(HeadlessSteps(steps))
+        .add_systems(Update, exit_after_headless_steps)
+        .add_plugins((bevy_stl::StlPlugin, RobotPlugin))
+        .insert_resource(configs)
+        .init_resource::<SpawnedRobots>()
+        .add_event::<ReloadRobots>()
+        .add_systems(Startup, spawn_robots);
+        app
+    }
+
+    #[test]
+    fn sample_robot_runs_headless_without_non_finite_transforms() {
+        let mut app = headless_app(RobotSpawnConfigs::default(), 100);
+        for _ in 0..99 {
+            app.update();
+            assert_eq!(app.should_exit(), None);
         }
-    } else {
-        Color::srgba(0.8, 0.8, 0.8, 1.0)
-    };
+        app.update();
 
-    materials.add(StandardMaterial {
-        base_color: color,
-        ..Default::default()
-    })
-}
+        assert_eq!(app.world().resource::<SpawnedRobots>().0.len(), 1);
+        assert_eq!(app.should_exit(), Some(AppExit::Success));
+    }
+
+    #[test]
+    fn spawns_count_copies_spaced_apart() {
+        let configs = RobotSpawnConfigs(vec![RobotSpawnConfig {
+            count: 3,
+            up_axis: UpAxis::Y,
+            base_transform: Transform::IDENTITY,
+            ..default()
+        }]);
+        let mut app = headless_app(configs, 100);
+        app.update();
 
-fn urdf_to_transform(origin: &Pose, geometry: &Geometry) -> Transform {
-    let mut pos = origin.xyz;
-    let rot = origin.rpy;
-
-    let mut scale = Vec3::ONE;
-
-    if let Geometry::Mesh {
-        scale: Some(mesh_scale),
-        ..
-    } = geometry
-    {
-        scale = Vec3::new(
-            mesh_scale[0] as f32,
-            mesh_scale[1] as f32,
-            mesh_scale[2] as f32,
+        let world = app.world_mut();
+        let mut positions: Vec<Vec3> = world
+            .query_filtered::<&Transform, With<Robot>>()
+            .iter(world)
+            .map(|transform| transform.translation)
+            .collect();
+        positions.sort_by(|a, b| a.x.total_cmp(&b.x));
+        assert_eq!(
+            positions,
+            [
+                Vec3::ZERO,
+                Vec3::new(0.5, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0)
+            ]
         );
+    }
+
+    #[test]
+    fn spawns_distinct_urdfs_with_their_own_links() {
+        let cart = std::env::temp_dir().join(format!("cart-{}.urdf", std::process::id()));
+        std::fs::write(
+            &cart,
+            r#"<robot name="cart">
+                 <link name="base_link"/>
+                 <link name="wheel"/>
+                 <joint name="axle" type="continuous">
+                   <parent link="base_link"/>
+                   <child link="wheel"/>
+                 </joint>
+               </robot>"#,
+        )
+        .unwrap();
+        let configs = RobotSpawnConfigs(vec![
+            RobotSpawnConfig::default(),
+            RobotSpawnConfig {
+                urdf_path: cart.to_string_lossy().into_owned(),
+                ..default()
+            },
+        ]);
+        let mut app = headless_app(configs, 100);
+        app.update();
+        std::fs::remove_file(&cart).unwrap();
 
-        pos[0] *= mesh_scale[0];
-        pos[1] *= mesh_scale[1];
-        pos[2] *= mesh_scale[2];
+        let world = app.world();
+        let robots = &world.resource::<SpawnedRobots>().0;
+        let link_counts: Vec<usize> = robots.iter().map(|robot| robot.links.len()).collect();
+        assert_eq!(link_counts, [7, 2]);
+        // Both have a `base_link`, each below its own root.
+        for robot in robots {
+            let base_link = robot.links["base_link"];
+            assert_eq!(world.get::<Parent>(base_link).unwrap().get(), robot.root);
+        }
     }
 
-    Transform {
-        translation: Vec3::new(pos[0] as f32, pos[1] as f32, pos[2] as f32),
-        rotation: Quat::from_euler(EulerRot::XYZ, rot[0] as f32, rot[1] as f32, rot[2] as f32),
-        scale,
+    #[test]
+    fn non_finite_transform_fails_headless_run() {
+        let mut app = headless_app(RobotSpawnConfigs(Vec::new()), 3);
+        app.world_mut()
+            .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                f32::NAN,
+                0.0,
+                0.0,
+            )));
+        for _ in 0..3 {
+            app.update();
+        }
+
+        assert_eq!(app.should_exit(), Some(AppExit::error()));
     }
 }
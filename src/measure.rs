@@ -0,0 +1,272 @@
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+
+use crate::camera::MainCamera;
+use crate::robot::RobotLink;
+
+pub struct MeasurePlugin;
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Measurement>()
+            .add_systems(Startup, spawn_measurement_label)
+            .add_systems(
+                Update,
+                (
+                    toggle_measurement,
+                    pick_measured_links,
+                    draw_measurement,
+                    update_measurement_label,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Measures the distance between two link origins. Toggled with M; while
+/// enabled, right-clicking a link selects it, and a third pick starts over.
+#[derive(Resource, Default)]
+pub struct Measurement {
+    pub enabled: bool,
+    pub links: Vec<Entity>,
+}
+
+#[derive(Component)]
+struct MeasurementLabel;
+
+const ENDPOINT_RADIUS: f32 = 0.005;
+
+/// Distance between the origins of two link frames.
+pub fn link_distance(a: &GlobalTransform, b: &GlobalTransform) -> f32 {
+    a.translation().distance(b.translation())
+}
+
+fn toggle_measurement(keys: Res<ButtonInput<KeyCode>>, mut measurement: ResMut<Measurement>) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        measurement.enabled = !measurement.enabled;
+        measurement.links.clear();
+        info!(
+            "Measurement {}",
+            if measurement.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+}
+
+fn pick_measured_links(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut measurement: ResMut<Measurement>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), MainCamera>,
+    parts: Query<(&Aabb, &GlobalTransform, &InheritedVisibility, &Parent)>,
+    links: Query<(), With<RobotLink>>,
+) {
+    if !measurement.enabled || !mouse_buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+
+    let Ok(window) = primary_window.get_single() else {
+        return;
+    };
+    if window.cursor.grab_mode != CursorGrabMode::None {
+        return;
+    }
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+    let Some(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+
+    // Links have no colliders, so pick against the bounds of their visible
+    // parts and take the nearest hit.
+    let hit = parts
+        .iter()
+        .filter(|(_, _, visibility, parent)| visibility.get() && links.contains(parent.get()))
+        .filter_map(|(aabb, transform, _, parent)| {
+            ray_aabb_distance(ray, aabb, transform).map(|distance| (distance, parent.get()))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, link)| link);
+
+    let Some(link) = hit else {
+        measurement.links.clear();
+        return;
+    };
+    if measurement.links.len() == 2 {
+        measurement.links.clear();
+    }
+    measurement.links.push(link);
+}
+
+/// Distance along `ray` to where it enters `aabb`, placed by `transform`.
+fn ray_aabb_distance(ray: Ray3d, aabb: &Aabb, transform: &GlobalTransform) -> Option<f32> {
+    // An affine map keeps the ray parameter, so the slab test can run in the
+    // box's local frame and still yield a world-space distance.
+    let world_to_local = transform.affine().inverse();
+    let origin = world_to_local.transform_point3(ray.origin);
+    let direction = world_to_local.transform_vector3(*ray.direction);
+
+    let min = Vec3::from(aabb.min());
+    let max = Vec3::from(aabb.max());
+    let inverse = direction.recip();
+    let mut near = f32::NEG_INFINITY;
+    let mut far = f32::INFINITY;
+    for axis in 0..3 {
+        // A ray parallel to a slab is inside it everywhere or nowhere. The
+        // slab test would multiply 0 by infinity here and get NaN.
+        if direction[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (min[axis] - origin[axis]) * inverse[axis];
+        let t2 = (max[axis] - origin[axis]) * inverse[axis];
+        near = near.max(t1.min(t2));
+        far = far.min(t1.max(t2));
+    }
+
+    (near <= far && far >= 0.0).then_some(near.max(0.0))
+}
+
+fn draw_measurement(
+    mut gizmos: Gizmos,
+    measurement: Res<Measurement>,
+    transforms: Query<&GlobalTransform>,
+) {
+    for &link in &measurement.links {
+        if let Ok(transform) = transforms.get(link) {
+            gizmos.sphere(
+                transform.translation(),
+                Quat::IDENTITY,
+                ENDPOINT_RADIUS,
+                css::FUCHSIA,
+            );
+        }
+    }
+
+    if let [a, b] = measurement.links[..] {
+        if let Ok([a, b]) = transforms.get_many([a, b]) {
+            gizmos.line(a.translation(), b.translation(), css::FUCHSIA);
+        }
+    }
+}
+
+fn spawn_measurement_label(mut commands: Commands) {
+    commands.spawn((
+        MeasurementLabel,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            ..Default::default()
+        }),
+    ));
+}
+
+/// Shows the distance next to the midpoint of the measured line.
+fn update_measurement_label(
+    measurement: Res<Measurement>,
+    transforms: Query<&GlobalTransform>,
+    cameras: Query<(&Camera, &GlobalTransform), MainCamera>,
+    mut labels: Query<(&mut Text, &mut Style, &mut Visibility), With<MeasurementLabel>>,
+) {
+    let Ok((mut text, mut style, mut visibility)) = labels.get_single_mut() else {
+        return;
+    };
+
+    let endpoints = match measurement.links[..] {
+        [a, b] => transforms.get_many([a, b]).ok(),
+        _ => None,
+    };
+    let screen_position = endpoints.zip(cameras.get_single().ok()).and_then(
+        |([a, b], (camera, camera_transform))| {
+            let midpoint = (a.translation() + b.translation()) / 2.0;
+            camera
+                .world_to_viewport(camera_transform, midpoint)
+                .map(|position| (position, link_distance(a, b)))
+        },
+    );
+
+    let Some((position, distance)) = screen_position else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    *visibility = Visibility::Inherited;
+    text.sections[0].value = format!("{distance:.3} m");
+    style.left = Val::Px(position.x);
+    style.top = Val::Px(position.y);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::from_min_max(Vec3::splat(-1.0), Vec3::splat(1.0))
+    }
+
+    fn ray(origin: Vec3, direction: Vec3) -> Ray3d {
+        Ray3d::new(origin, direction)
+    }
+
+    #[test]
+    fn ray_hits_box() {
+        let transform = GlobalTransform::from_translation(Vec3::new(0.0, 0.0, -5.0));
+        let distance = ray_aabb_distance(
+            ray(Vec3::new(0.3, 0.2, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+            &unit_box(),
+            &transform,
+        );
+        assert_eq!(distance, Some(4.0));
+    }
+
+    #[test]
+    fn ray_misses_box() {
+        let transform = GlobalTransform::from_translation(Vec3::new(0.0, 0.0, -5.0));
+        let beside = ray(Vec3::new(3.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let away = ray(Vec3::ZERO, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(ray_aabb_distance(beside, &unit_box(), &transform), None);
+        assert_eq!(ray_aabb_distance(away, &unit_box(), &transform), None);
+    }
+
+    #[test]
+    fn axis_parallel_ray_on_box_face() {
+        // The origin lies on the y and z faces' planes, where the slab test
+        // used to divide 0 by 0.
+        let along_face = ray(Vec3::new(-3.0, 1.0, -1.0), Vec3::X);
+        let distance = ray_aabb_distance(along_face, &unit_box(), &GlobalTransform::IDENTITY);
+        assert_eq!(distance, Some(2.0));
+
+        let above = ray(Vec3::new(-3.0, 1.5, 0.0), Vec3::X);
+        assert_eq!(
+            ray_aabb_distance(above, &unit_box(), &GlobalTransform::IDENTITY),
+            None
+        );
+    }
+
+    #[test]
+    fn distance_between_link_origins() {
+        let a = GlobalTransform::from_translation(Vec3::new(1.0, 2.0, 3.0));
+        let b = GlobalTransform::from(
+            Transform::from_xyz(4.0, 6.0, 3.0).with_rotation(Quat::from_rotation_y(1.0)),
+        );
+        assert_eq!(link_distance(&a, &b), 5.0);
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::robot::{JointState, RobotJoint, RobotLink};
+
+/// Records ground-truth joint states and link poses to a CSV file every
+/// fixed timestep, while `JointRecorderSettings::path` is set.
+///
+/// Each row holds the time, then `<joint>/position` and `<joint>/velocity`
+/// from the `JointState` of every movable joint, then `<link>/x`, `y`, `z`, `qx`, `qy`, `qz`
+/// and `qw` for the world pose of every link. The columns are fixed by the
+/// joints and links present at the first row; cells of any that are missing
+/// later, such as while robots reload, are left empty.
+pub struct JointRecorderPlugin;
+
+impl Plugin for JointRecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JointRecorderSettings>()
+            .init_resource::<JointRecorder>()
+            .add_systems(
+                FixedUpdate,
+                record_joint_states
+                    .run_if(|settings: Res<JointRecorderSettings>| settings.path.is_some()),
+            )
+            .add_systems(Last, flush_recording_on_exit);
+    }
+}
+
+/// CSV file to record to, and how many rows to buffer before writing them
+/// out. Buffered rows are also written when the app exits.
+#[derive(Resource)]
+pub struct JointRecorderSettings {
+    pub path: Option<PathBuf>,
+    pub flush_every: u32,
+}
+
+impl Default for JointRecorderSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            flush_every: 64,
+        }
+    }
+}
+
+const POSE_COLUMNS: [&str; 7] = ["x", "y", "z", "qx", "qy", "qz", "qw"];
+
+/// The open recording, created with the first row.
+#[derive(Resource, Default)]
+struct JointRecorder(Option<Recording>);
+
+struct Recording {
+    writer: BufWriter<File>,
+    joints: Vec<String>,
+    links: Vec<String>,
+    unflushed: u32,
+}
+
+fn header(joints: &[String], links: &[String]) -> String {
+    let joints = joints
+        .iter()
+        .flat_map(|joint| [format!("{joint}/position"), format!("{joint}/velocity")]);
+    let links = links
+        .iter()
+        .flat_map(|link| POSE_COLUMNS.map(|column| format!("{link}/{column}")));
+    std::iter::once("time".to_string())
+        .chain(joints)
+        .chain(links)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn record_joint_states(
+    time: Res<Time>,
+    settings: Res<JointRecorderSettings>,
+    mut recorder: ResMut<JointRecorder>,
+    joints: Query<(&RobotJoint, &JointState)>,
+    links: Query<(&Name, &GlobalTransform), With<RobotLink>>,
+) {
+    let Some(path) = &settings.path else {
+        return;
+    };
+
+    let states: HashMap<String, JointState> = joints
+        .iter()
+        .filter(|(joint, _)| joint.is_movable())
+        .map(|(joint, state)| (joint.name.clone(), *state))
+        .collect();
+    let poses: HashMap<String, Transform> = links
+        .iter()
+        .map(|(name, transform)| (name.to_string(), transform.compute_transform()))
+        .collect();
+
+    if recorder.0.is_none() {
+        // Waits for the robots to spawn, so the header names their joints.
+        if states.is_empty() {
+            return;
+        }
+        let mut joints: Vec<String> = states.keys().cloned().collect();
+        let mut links: Vec<String> = poses.keys().cloned().collect();
+        joints.sort();
+        links.sort();
+
+        let file = match File::create(path) {
+            Ok(file) => file,
+            Err(err) => {
+                error!("Failed to create recording {}: {err}", path.display());
+                return;
+            }
+        };
+        let mut writer = BufWriter::new(file);
+        if let Err(err) = writeln!(writer, "{}", header(&joints, &links)) {
+            error!("Failed to write recording {}: {err}", path.display());
+            return;
+        }
+        info!("Recording joint states to {}", path.display());
+        recorder.0 = Some(Recording {
+            writer,
+            joints,
+            links,
+            unflushed: 0,
+        });
+    }
+    let Some(recording) = &mut recorder.0 else {
+        return;
+    };
+
+    let mut row = vec![time.elapsed_seconds().to_string()];
+    for joint in &recording.joints {
+        match states.get(joint) {
+            Some(state) => row.extend([state.position.to_string(), state.velocity.to_string()]),
+            None => row.extend([String::new(), String::new()]),
+        }
+    }
+    for link in &recording.links {
+        match poses.get(link) {
+            Some(pose) => {
+                let (t, r) = (pose.translation, pose.rotation);
+                row.extend([t.x, t.y, t.z, r.x, r.y, r.z, r.w].map(|value| value.to_string()));
+            }
+            None => row.extend(POSE_COLUMNS.map(|_| String::new())),
+        }
+    }
+    if let Err(err) = writeln!(recording.writer, "{}", row.join(",")) {
+        error!("Failed to write recording {}: {err}", path.display());
+        return;
+    }
+    recording.unflushed += 1;
+    if recording.unflushed >= settings.flush_every {
+        recording.unflushed = 0;
+        if let Err(err) = recording.writer.flush() {
+            error!("Failed to write recording {}: {err}", path.display());
+        }
+    }
+}
+
+fn flush_recording_on_exit(mut exit: EventReader<AppExit>, mut recorder: ResMut<JointRecorder>) {
+    if exit.read().count() == 0 {
+        return;
+    }
+    if let Some(recording) = &mut recorder.0 {
+        if let Err(err) = recording.writer.flush() {
+            error!("Failed to write recording: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use urdf_rs::JointType;
+
+    use super::*;
+
+    #[test]
+    fn records_one_row_per_step() {
+        let path = std::env::temp_dir().join(format!("recording-{}.csv", std::process::id()));
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<JointRecorder>();
+        world.init_resource::<Events<AppExit>>();
+        world.insert_resource(JointRecorderSettings {
+            path: Some(path.clone()),
+            flush_every: 2,
+        });
+        for name in ["arm/shoulder", "arm/elbow"] {
+            world.spawn((
+                RobotJoint {
+                    name: name.to_string(),
+                    joint_type: JointType::Revolute,
+                    axis: Vec3::Y,
+                    lower: -1.0,
+                    upper: 1.0,
+                    position: 0.0,
+                },
+                JointState::default(),
+                RobotLink,
+                Name::new(name.replace("arm/", "arm/link_")),
+                GlobalTransform::IDENTITY,
+            ));
+        }
+
+        for _ in 0..4 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(250));
+            world.run_system_once(record_joint_states);
+        }
+        world
+            .resource_mut::<Events<AppExit>>()
+            .send(AppExit::Success);
+        world.run_system_once(flush_recording_on_exit);
+        let csv = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let csv = csv.unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 4);
+        // Time, two joints with two columns and two links with seven.
+        let columns = 1 + 2 * 2 + 2 * 7;
+        assert!(lines.iter().all(|line| line.split(',').count() == columns));
+        assert!(lines[0].starts_with("time,arm/elbow/position,arm/elbow/velocity"));
+    }
+}
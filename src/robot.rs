@@ -0,0 +1,2257 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use urdf_rs::{Geometry, JointType, Pose};
+
+use crate::inertia::{
+    equivalent_ellipsoid, fix_inertia, inertia_matrix, principal_axes, validate_inertia,
+    InvalidInertia,
+};
+
+pub struct RobotPlugin;
+
+impl Plugin for RobotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LinkColorOverrides>()
+            .init_resource::<HiddenLinks>()
+            .init_resource::<LinkMaterials>()
+            .init_resource::<SpawnOverlapCheck>()
+            .init_resource::<RenderMode>()
+            .init_resource::<CollisionColors>()
+            .init_resource::<CollisionMaterials>()
+            .init_resource::<RobotAssetCache>()
+            .init_resource::<PackagePathResolver>()
+            .init_resource::<MassOverrides>()
+            .add_systems(
+                Update,
+                (
+                    process_urdf_visuals,
+                    process_urdf_collisions,
+                    refresh_link_materials.run_if(
+                        resource_changed::<LinkColorOverrides>
+                            .or_else(resource_changed::<LinkMaterials>),
+                    ),
+                    (cycle_render_mode, apply_render_mode).chain(),
+                    reset_to_initial_pose,
+                    (apply_mimic_joints, apply_joint_positions, read_joint_states).chain(),
+                    report_robot_bounds,
+                    check_spawn_overlaps,
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Robot;
+
+#[derive(Component)]
+pub struct RobotLink;
+
+/// The URDF joint attaching a link to its parent, stored on the child link.
+/// The joint frame coincides with the child link's frame. `lower` and
+/// `upper` are the URDF position limits. `position` is the joint's current
+/// angle or offset, applied kinematically on top of the link's `InitialPose`.
+/// `name` is `<robot>/<joint>`, as link entities are `Name`d `<robot>/<link>`.
+#[derive(Component, Clone)]
+pub struct RobotJoint {
+    pub name: String,
+    pub joint_type: JointType,
+    pub axis: Vec3,
+    pub lower: f32,
+    pub upper: f32,
+    pub position: f32,
+}
+
+impl RobotJoint {
+    /// Whether `position` moves the child link. Planar and floating joints
+    /// have more degrees of freedom than a single `position` can hold, so
+    /// their child links stay at their `InitialPose`, like fixed joints.
+    pub fn is_movable(&self) -> bool {
+        matches!(
+            self.joint_type,
+            JointType::Revolute | JointType::Continuous | JointType::Prismatic
+        )
+    }
+
+    /// The child link's transform with the joint at `position`, given the
+    /// link's `InitialPose`. `None` for joints that don't move.
+    pub fn link_transform(&self, initial: &Transform, position: f32) -> Option<Transform> {
+        let axis = self.axis.try_normalize()?;
+
+        match self.joint_type {
+            JointType::Revolute | JointType::Continuous => Some(initial.mul_transform(
+                Transform::from_rotation(Quat::from_axis_angle(axis, position)),
+            )),
+            JointType::Prismatic => {
+                Some(initial.mul_transform(Transform::from_translation(axis * position)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Clamps `position` to the joint limits. Continuous joints are unbounded.
+    pub fn clamp_position(&self, position: f32) -> f32 {
+        match self.joint_type {
+            JointType::Continuous => position,
+            _ => position.max(self.lower).min(self.upper),
+        }
+    }
+}
+
+/// A joint's position read back from its child link's `Transform`, relative
+/// to the link's `InitialPose`, and how fast it changed over the last frame.
+/// Revolute joints report the angle about their axis in (-π, π], and
+/// continuous joints the total angle turned, unwrapped across frames.
+/// Prismatic joints report the offset along the axis. Other joints stay at
+/// zero.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct JointState {
+    pub position: f32,
+    pub velocity: f32,
+}
+
+/// Angle of `rel_rotation` about `axis`, in (-π, π]. Any rotation about other
+/// axes is ignored.
+pub fn joint_angle(rel_rotation: Quat, axis: Vec3) -> f32 {
+    let twist = rel_rotation.xyz().dot(axis.normalize());
+    let angle = 2.0 * twist.atan2(rel_rotation.w);
+    if angle > std::f32::consts::PI {
+        angle - std::f32::consts::TAU
+    } else if angle <= -std::f32::consts::PI {
+        angle + std::f32::consts::TAU
+    } else {
+        angle
+    }
+}
+
+/// The angle equal to `wrapped` modulo 2π that is closest to `previous`, so
+/// an angle read back every frame keeps counting past ±π.
+pub fn unwrap_angle(previous: f32, wrapped: f32) -> f32 {
+    let step = (wrapped - previous + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+        - std::f32::consts::PI;
+    previous + step
+}
+
+/// A URDF `<mimic>` joint: its `RobotJoint::position` follows the joint on
+/// the `source` link as `multiplier * source + offset`.
+#[derive(Component)]
+pub struct MimicJoint {
+    pub source: Entity,
+    pub multiplier: f32,
+    pub offset: f32,
+}
+
+#[derive(Component)]
+struct RobotPart;
+
+#[derive(Component)]
+struct UrdfCollision {
+    geometry: Geometry,
+    origin: Pose,
+    base_dir: PathBuf,
+    robot: Entity,
+}
+
+#[derive(Component)]
+pub struct UrdfVisual {
+    geometry: Geometry,
+    material: Option<urdf_rs::Material>,
+    origin: Pose,
+    base_dir: PathBuf,
+    link_name: String,
+}
+
+/// Base colors to use instead of the URDF material for links with these
+/// names. Changes apply to already spawned robots.
+#[derive(Resource, Default)]
+pub struct LinkColorOverrides(pub HashMap<String, Color>);
+
+/// Surface properties of link materials. The URDF material or a
+/// `LinkColorOverrides` entry still sets the base color.
+#[derive(Clone, Copy)]
+pub struct MaterialConfig {
+    pub metallic: f32,
+    pub roughness: f32,
+}
+
+impl Default for MaterialConfig {
+    fn default() -> Self {
+        let material = StandardMaterial::default();
+        Self {
+            metallic: material.metallic,
+            roughness: material.perceptual_roughness,
+        }
+    }
+}
+
+/// `MaterialConfig` for every link, except the links named in `overrides`.
+/// Changes apply to already spawned robots.
+#[derive(Resource, Default)]
+pub struct LinkMaterials {
+    pub default: MaterialConfig,
+    pub overrides: HashMap<String, MaterialConfig>,
+}
+
+impl LinkMaterials {
+    fn get(&self, link_name: &str) -> MaterialConfig {
+        self.overrides
+            .get(link_name)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Masses in kg to use instead of the URDF's for links with these names.
+/// The link's inertia tensor is scaled by the same factor, keeping its shape.
+/// Applies to robots spawned afterwards.
+#[derive(Resource, Default)]
+pub struct MassOverrides(pub HashMap<String, f32>);
+
+impl MassOverrides {
+    fn apply(&self, robot: &mut urdf_rs::Robot) {
+        for link in &mut robot.links {
+            let Some(&mass) = self.0.get(&link.name) else {
+                continue;
+            };
+            let inertial = &mut link.inertial;
+            // A massless link has no tensor worth scaling.
+            if inertial.mass.value > 0.0 {
+                let scale = f64::from(mass) / inertial.mass.value;
+                let inertia = &mut inertial.inertia;
+                for moment in [
+                    &mut inertia.ixx,
+                    &mut inertia.ixy,
+                    &mut inertia.ixz,
+                    &mut inertia.iyy,
+                    &mut inertia.iyz,
+                    &mut inertia.izz,
+                ] {
+                    *moment *= scale;
+                }
+            }
+            inertial.mass.value = mass.into();
+        }
+    }
+}
+
+/// Links whose visual meshes are hidden, by name, whatever the `RenderMode`.
+/// Their collision overlays are unaffected. Changes apply to already spawned
+/// robots.
+#[derive(Resource, Default)]
+pub struct HiddenLinks(pub HashSet<String>);
+
+/// Which URDF geometry is drawn: the `<visual>` meshes, the `<collision>`
+/// shapes as translucent overlays, or both. Cycled with V.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+    #[default]
+    Visual,
+    Collision,
+    Both,
+}
+
+impl RenderMode {
+    fn visual_visibility(self) -> Visibility {
+        match self {
+            RenderMode::Visual | RenderMode::Both => Visibility::Inherited,
+            RenderMode::Collision => Visibility::Hidden,
+        }
+    }
+
+    fn collision_visibility(self) -> Visibility {
+        match self {
+            RenderMode::Collision | RenderMode::Both => Visibility::Inherited,
+            RenderMode::Visual => Visibility::Hidden,
+        }
+    }
+}
+
+fn visual_visibility(
+    urdf_visual: &UrdfVisual,
+    render_mode: RenderMode,
+    hidden_links: &HiddenLinks,
+) -> Visibility {
+    if hidden_links.0.contains(&urdf_visual.link_name) {
+        Visibility::Hidden
+    } else {
+        render_mode.visual_visibility()
+    }
+}
+
+/// Translucent colors for collision overlays. Each robot takes the color at
+/// its `SpawnIndex`, wrapping around, so robots can be told apart.
+#[derive(Resource)]
+pub struct CollisionColors(pub Vec<Color>);
+
+impl Default for CollisionColors {
+    fn default() -> Self {
+        Self(vec![
+            Color::srgba(1.0, 0.5, 0.0, 0.4),
+            Color::srgba(0.0, 0.8, 1.0, 0.4),
+            Color::srgba(1.0, 0.0, 0.8, 0.4),
+            Color::srgba(0.4, 1.0, 0.0, 0.4),
+            Color::srgba(1.0, 0.9, 0.0, 0.4),
+            Color::srgba(0.6, 0.3, 1.0, 0.4),
+        ])
+    }
+}
+
+/// Position of a robot in spawn order, counted from 0. It picks the robot's
+/// collision overlay color, so colors stay put when robots are reloaded.
+/// Robots without one use the first color.
+#[derive(Component, Clone, Copy)]
+pub struct SpawnIndex(pub usize);
+
+/// Collision overlay material of each `CollisionColors` entry.
+#[derive(Resource, Default)]
+struct CollisionMaterials(HashMap<usize, Handle<StandardMaterial>>);
+
+/// Filesystem roots for ROS packages, used to resolve `package://name/...`
+/// mesh URIs.
+#[derive(Resource, Default)]
+pub struct PackagePathResolver(pub HashMap<String, PathBuf>);
+
+#[derive(Debug)]
+pub struct UnknownPackage(pub String);
+
+impl std::fmt::Display for UnknownPackage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no path configured for package `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownPackage {}
+
+impl PackagePathResolver {
+    /// Rewrites a `package://` URI to a path under the package's root.
+    /// Other filenames are returned unchanged.
+    pub fn resolve(&self, filename: &str) -> Result<PathBuf, UnknownPackage> {
+        let Some(uri) = filename.strip_prefix("package://") else {
+            return Ok(PathBuf::from(filename));
+        };
+
+        let (package, relative) = uri.split_once('/').unwrap_or((uri, ""));
+        let root = self
+            .0
+            .get(package)
+            .ok_or_else(|| UnknownPackage(package.to_string()))?;
+        Ok(root.join(relative))
+    }
+
+    /// Path of a URDF mesh: `package://` URIs are resolved like
+    /// [`Self::resolve`], and other relative paths against `base_dir`.
+    fn mesh_path(&self, filename: &str, base_dir: &Path) -> Result<PathBuf, UnknownPackage> {
+        if filename.starts_with("package://") {
+            self.resolve(filename)
+        } else {
+            Ok(base_dir.join(filename))
+        }
+    }
+}
+
+/// Mesh and material handles shared by every robot, so identical visuals
+/// across robots reuse one asset and can be batched together.
+#[derive(Resource, Default)]
+struct RobotAssetCache {
+    meshes: HashMap<MeshKey, Handle<Mesh>>,
+    materials: HashMap<MaterialKey, Handle<StandardMaterial>>,
+}
+
+/// URDF geometry with its dimensions as raw bits, so it can be hashed.
+/// Mesh files are keyed by their resolved path.
+#[derive(PartialEq, Eq, Hash)]
+enum MeshKey {
+    File(PathBuf),
+    Box([u64; 3]),
+    Cylinder { radius: u64, length: u64 },
+    Capsule { radius: u64, length: u64 },
+    Sphere { radius: u64 },
+}
+
+impl MeshKey {
+    /// Key of `geometry`, or `None`, with a message logged, for meshes that
+    /// can't be loaded.
+    fn new(geometry: &Geometry, base_dir: &Path, resolver: &PackagePathResolver) -> Option<Self> {
+        Some(match geometry {
+            Geometry::Mesh { filename, .. } => {
+                if !is_supported_mesh(filename) {
+                    warn!(
+                        "Unsupported mesh extension .{} ({filename})",
+                        mesh_extension(filename)
+                    );
+                    return None;
+                }
+                match resolver.mesh_path(filename, base_dir) {
+                    Ok(path) => MeshKey::File(path),
+                    Err(err) => {
+                        error!("Cannot load mesh {filename}: {err}");
+                        return None;
+                    }
+                }
+            }
+            Geometry::Box { size } => MeshKey::Box(size.map(f64::to_bits)),
+            Geometry::Cylinder { radius, length } => MeshKey::Cylinder {
+                radius: radius.to_bits(),
+                length: length.to_bits(),
+            },
+            Geometry::Capsule { radius, length } => MeshKey::Capsule {
+                radius: radius.to_bits(),
+                length: length.to_bits(),
+            },
+            Geometry::Sphere { radius } => MeshKey::Sphere {
+                radius: radius.to_bits(),
+            },
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct MaterialKey {
+    color: [u32; 4],
+    texture: Option<PathBuf>,
+    metallic: u32,
+    roughness: u32,
+}
+
+impl RobotAssetCache {
+    fn mesh(
+        &mut self,
+        geometry: &Geometry,
+        base_dir: &Path,
+        resolver: &PackagePathResolver,
+        asset_server: &AssetServer,
+        meshes: &mut Assets<Mesh>,
+    ) -> Option<Handle<Mesh>> {
+        let key = MeshKey::new(geometry, base_dir, resolver)?;
+        if let Some(handle) = self.meshes.get(&key) {
+            return Some(handle.clone());
+        }
+
+        let handle = match &key {
+            MeshKey::File(path) => asset_server.load(path.clone()),
+            _ => meshes.add(primitive_mesh(geometry)?),
+        };
+        self.meshes.insert(key, handle.clone());
+        Some(handle)
+    }
+
+    fn material(
+        &mut self,
+        color: Color,
+        texture: Option<PathBuf>,
+        config: MaterialConfig,
+        asset_server: &AssetServer,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        let key = MaterialKey {
+            color: color.to_srgba().to_f32_array().map(f32::to_bits),
+            texture,
+            metallic: config.metallic.to_bits(),
+            roughness: config.roughness.to_bits(),
+        };
+        self.materials
+            .entry(key)
+            .or_insert_with_key(|key| {
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    base_color_texture: key
+                        .texture
+                        .as_ref()
+                        .map(|texture| asset_server.load(texture.clone())),
+                    metallic: config.metallic,
+                    perceptual_roughness: config.roughness,
+                    ..Default::default()
+                })
+            })
+            .clone()
+    }
+}
+
+/// The uniform solid ellipsoid with the same mass and inertia as a link.
+/// `frame` places it in the link frame: centred on the center of mass and
+/// aligned with the principal axes. Only links with a valid, non-zero
+/// inertial get one.
+#[derive(Component)]
+pub struct InertiaEllipsoid {
+    pub frame: Transform,
+    pub semi_axes: Vec3,
+}
+
+impl InertiaEllipsoid {
+    fn from_inertial(inertial: &urdf_rs::Inertial) -> Option<Self> {
+        if inertial.mass.value <= 0.0 {
+            return None;
+        }
+
+        let (moments, axes) = principal_axes(inertia_matrix(&inertial.inertia));
+        let principal = Quat::from_mat3(&axes.as_mat3());
+        Some(Self {
+            frame: pose_to_transform(&inertial.origin)
+                .mul_transform(Transform::from_rotation(principal)),
+            semi_axes: equivalent_ellipsoid(inertial.mass.value, moments).as_vec3(),
+        })
+    }
+}
+
+/// Transform an entity was spawned with, restored by pressing R.
+#[derive(Component)]
+pub struct InitialPose(pub Transform);
+
+/// Which axis a robot description treats as up. URDF is Z-up by convention.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum UpAxis {
+    Y,
+    #[default]
+    Z,
+}
+
+/// Rotation taking a robot authored with `up` as its up axis into Bevy's
+/// Y-up world. Apply it to the robot's base transform.
+pub fn up_axis_correction(up: UpAxis) -> Quat {
+    match up {
+        UpAxis::Y => Quat::IDENTITY,
+        UpAxis::Z => Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
+    }
+}
+
+/// Entities created for a single spawned robot.
+#[derive(Debug, Clone)]
+pub struct RobotHandle {
+    pub root: Entity,
+    pub links: HashMap<String, Entity>,
+}
+
+#[derive(Debug)]
+pub enum SpawnRobotError {
+    Urdf(urdf_rs::UrdfError),
+    MissingParentLink(String),
+    MissingChildLink(String),
+    /// A mesh file no `Mesh` asset loader can read.
+    UnsupportedGeometry(String),
+    /// Links on a joint cycle, which no root link leads to.
+    JointCycle(Vec<String>),
+    #[cfg(not(feature = "xacro"))]
+    XacroUnsupported,
+}
+
+impl std::fmt::Display for SpawnRobotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpawnRobotError::Urdf(err) => write!(f, "failed to read URDF: {err}"),
+            SpawnRobotError::MissingParentLink(link) => {
+                write!(f, "joint parent link `{link}` does not exist")
+            }
+            SpawnRobotError::MissingChildLink(link) => {
+                write!(f, "joint child link `{link}` does not exist")
+            }
+            SpawnRobotError::UnsupportedGeometry(filename) => {
+                write!(f, "unsupported mesh format for `{filename}`")
+            }
+            SpawnRobotError::JointCycle(links) => {
+                write!(
+                    f,
+                    "joint cycle through links that no root leads to: {}",
+                    links.join(", ")
+                )
+            }
+            #[cfg(not(feature = "xacro"))]
+            SpawnRobotError::XacroUnsupported => {
+                write!(f, "xacro files need the `xacro` feature")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpawnRobotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SpawnRobotError::Urdf(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<urdf_rs::UrdfError> for SpawnRobotError {
+    fn from(err: urdf_rs::UrdfError) -> Self {
+        SpawnRobotError::Urdf(err)
+    }
+}
+
+/// Spawns the robot described by the URDF at `path`. Each link is parented to
+/// the link above it in the joint tree and placed at its joint origin.
+pub fn spawn_robot_from_path(
+    commands: &mut Commands,
+    path: &str,
+    base_transform: Transform,
+    invalid_inertia: InvalidInertia,
+    mass_overrides: &MassOverrides,
+) -> Result<RobotHandle, SpawnRobotError> {
+    let urdf = read_robot_description(path)?;
+
+    // Texture paths are relative to the URDF, not the asset root, so keep an
+    // absolute directory around for the asset server to resolve against.
+    let urdf_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    let base_dir = std::fs::canonicalize(urdf_dir).unwrap_or_else(|_| urdf_dir.to_path_buf());
+
+    spawn_robot_from_str(
+        commands,
+        &urdf,
+        &base_dir,
+        base_transform,
+        invalid_inertia,
+        mass_overrides,
+    )
+}
+
+/// Spawns the robot described by the URDF text `urdf`, like
+/// [`spawn_robot_from_path`] without touching the filesystem. Relative mesh
+/// and texture paths are resolved against `base_dir`, which stands in for the
+/// URDF's directory.
+pub fn spawn_robot_from_str(
+    commands: &mut Commands,
+    urdf: &str,
+    base_dir: &Path,
+    base_transform: Transform,
+    invalid_inertia: InvalidInertia,
+    mass_overrides: &MassOverrides,
+) -> Result<RobotHandle, SpawnRobotError> {
+    let mut robot = urdf_rs::read_from_string(urdf)?;
+    validate_joint_links(&robot)?;
+    validate_geometry(&robot)?;
+    mass_overrides.apply(&mut robot);
+
+    for link in &mut robot.links {
+        let Err(err) = validate_inertia(link) else {
+            continue;
+        };
+        if invalid_inertia == InvalidInertia::Fix && fix_inertia(link) {
+            info!(
+                "Replaced the invalid inertia tensor of link `{}` ({err}) with a box inertia",
+                link.name
+            );
+        } else {
+            warn!("Link `{}` has an invalid inertia tensor: {err}", link.name);
+        }
+    }
+
+    let root = commands
+        .spawn((
+            Robot,
+            Name::new(robot.name.clone()),
+            InitialPose(base_transform),
+            TransformBundle::from_transform(base_transform),
+            VisibilityBundle::default(),
+        ))
+        .id();
+
+    let parent_joints: HashMap<&str, &urdf_rs::Joint> = robot
+        .joints
+        .iter()
+        .map(|joint| (joint.child.link.as_str(), joint))
+        .collect();
+
+    log_mass_properties(&robot, &parent_joints);
+    log_kinematic_tree(&robot, &parent_joints);
+
+    let mut links = HashMap::new();
+    for link in &robot.links {
+        // Invalid tensors were already reported above.
+        let inertia_ellipsoid = validate_inertia(link)
+            .ok()
+            .and_then(|()| InertiaEllipsoid::from_inertial(&link.inertial));
+
+        let transform = parent_joints
+            .get(link.name.as_str())
+            .map(|joint| pose_to_transform(&joint.origin))
+            .unwrap_or_default();
+
+        let entity = commands
+            .spawn((
+                RobotLink,
+                Name::new(format!("{}/{}", robot.name, link.name)),
+                InitialPose(transform),
+                TransformBundle::from_transform(transform),
+                VisibilityBundle::default(),
+            ))
+            .with_children(|parent| {
+                for collision in &link.collision {
+                    parent.spawn((
+                        RobotPart,
+                        UrdfCollision {
+                            geometry: collision.geometry.clone(),
+                            origin: collision.origin.clone(),
+                            base_dir: base_dir.to_path_buf(),
+                            robot: root,
+                        },
+                        TransformBundle::default(),
+                        VisibilityBundle::default(),
+                    ));
+                }
+
+                for visual in &link.visual {
+                    parent.spawn((
+                        RobotPart,
+                        UrdfVisual {
+                            geometry: visual.geometry.clone(),
+                            material: visual.material.clone(),
+                            origin: visual.origin.clone(),
+                            base_dir: base_dir.to_path_buf(),
+                            link_name: link.name.clone(),
+                        },
+                        TransformBundle::default(),
+                        VisibilityBundle::default(),
+                    ));
+                }
+            })
+            .id();
+
+        if let Some(inertia_ellipsoid) = inertia_ellipsoid {
+            commands.entity(entity).insert(inertia_ellipsoid);
+        }
+        links.insert(link.name.clone(), entity);
+    }
+
+    for link in &robot.links {
+        let entity = links[&link.name];
+
+        let parent = match parent_joints.get(link.name.as_str()) {
+            Some(joint) => {
+                let axis = joint.axis.xyz;
+                commands.entity(entity).insert(RobotJoint {
+                    name: format!("{}/{}", robot.name, joint.name),
+                    joint_type: joint.joint_type.clone(),
+                    axis: Vec3::new(axis[0] as f32, axis[1] as f32, axis[2] as f32),
+                    lower: joint.limit.lower as f32,
+                    upper: joint.limit.upper as f32,
+                    position: 0.0,
+                });
+                commands.entity(entity).insert(JointState::default());
+                links[&joint.parent.link]
+            }
+            None => root,
+        };
+        commands.entity(parent).add_child(entity);
+    }
+
+    let joint_links: HashMap<&str, Entity> = robot
+        .joints
+        .iter()
+        .map(|joint| (joint.name.as_str(), links[&joint.child.link]))
+        .collect();
+    for joint in &robot.joints {
+        let Some(mimic) = &joint.mimic else {
+            continue;
+        };
+        let Some(&source) = joint_links.get(mimic.joint.as_str()) else {
+            warn!(
+                "Joint `{}` mimics joint `{}`, which does not exist",
+                joint.name, mimic.joint
+            );
+            continue;
+        };
+        commands
+            .entity(links[&joint.child.link])
+            .insert(MimicJoint {
+                source,
+                multiplier: mimic.multiplier.unwrap_or(1.0) as f32,
+                offset: mimic.offset.unwrap_or(0.0) as f32,
+            });
+    }
+
+    Ok(RobotHandle { root, links })
+}
+
+/// Despawns a robot spawned by [`spawn_robot_from_path`] or
+/// [`spawn_robot_from_str`]. Links, joints and visual/collision parts all
+/// live in the hierarchy below `root`.
+pub fn despawn_robot(commands: &mut Commands, robot: RobotHandle) {
+    commands.entity(robot.root).despawn_recursive();
+}
+
+/// Finds a link entity by its `<robot>/<link>` name, or by the bare link name
+/// when only one robot is involved (the first match wins).
+pub fn find_link_by_name(
+    links: &Query<(Entity, &Name), With<RobotLink>>,
+    name: &str,
+) -> Option<Entity> {
+    links
+        .iter()
+        .find(|(_, link)| {
+            link.as_str() == name
+                || link
+                    .as_str()
+                    .rsplit_once('/')
+                    .is_some_and(|(_, link)| link == name)
+        })
+        .map(|(entity, _)| entity)
+}
+
+/// Reads a URDF, or with the `xacro` feature expands a `.xacro` file into one
+/// by running the ROS `xacro` tool.
+fn read_robot_description(path: &str) -> Result<String, SpawnRobotError> {
+    if Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension == "xacro")
+    {
+        #[cfg(feature = "xacro")]
+        return Ok(urdf_rs::utils::convert_xacro_to_urdf(path)?);
+        #[cfg(not(feature = "xacro"))]
+        return Err(SpawnRobotError::XacroUnsupported);
+    }
+
+    Ok(std::fs::read_to_string(path).map_err(urdf_rs::UrdfError::from)?)
+}
+
+/// The robot's total mass and its center of mass in the root frame, with
+/// every joint at its zero position, or `None` if it has no mass.
+fn mass_properties(
+    robot: &urdf_rs::Robot,
+    parent_joints: &HashMap<&str, &urdf_rs::Joint>,
+) -> Option<(f32, Vec3)> {
+    let mut total_mass = 0.0;
+    let mut weighted_center = Vec3::ZERO;
+    for link in &robot.links {
+        let mass = link.inertial.mass.value as f32;
+        let center = root_frame_transform(&link.name, parent_joints)
+            .transform_point(pose_to_transform(&link.inertial.origin).translation);
+        total_mass += mass;
+        weighted_center += center * mass;
+    }
+
+    (total_mass > 0.0).then(|| (total_mass, weighted_center / total_mass))
+}
+
+fn log_mass_properties(robot: &urdf_rs::Robot, parent_joints: &HashMap<&str, &urdf_rs::Joint>) {
+    match mass_properties(robot, parent_joints) {
+        Some((total_mass, center)) => info!(
+            "Robot `{}` has a total mass of {total_mass:.3} kg, center of mass {center:.3} in the root frame",
+            robot.name
+        ),
+        None => warn!("Robot `{}` has no mass", robot.name),
+    }
+}
+
+/// A robot's link tree with the joint above each link, one link per line,
+/// and the structure that keeps it from being a single tree.
+struct KinematicTree<'a> {
+    text: String,
+    roots: Vec<&'a str>,
+    /// Links that are the child of several joints.
+    shared_children: Vec<&'a str>,
+    /// Links hanging off a joint cycle rather than below any root.
+    unreached: Vec<&'a str>,
+}
+
+fn kinematic_tree<'a>(
+    robot: &'a urdf_rs::Robot,
+    parent_joints: &HashMap<&str, &urdf_rs::Joint>,
+) -> KinematicTree<'a> {
+    let mut child_joints: HashMap<&str, Vec<&urdf_rs::Joint>> = HashMap::new();
+    let mut child_links = HashSet::new();
+    let mut shared_children = Vec::new();
+    for joint in &robot.joints {
+        if !child_links.insert(joint.child.link.as_str()) {
+            shared_children.push(joint.child.link.as_str());
+        }
+        // A link with several parent joints hangs below the one spawning
+        // uses, so the tree matches the spawned hierarchy.
+        let is_parent_joint = parent_joints
+            .get(joint.child.link.as_str())
+            .is_some_and(|parent_joint| std::ptr::eq(*parent_joint, joint));
+        if !is_parent_joint {
+            continue;
+        }
+        child_joints
+            .entry(joint.parent.link.as_str())
+            .or_default()
+            .push(joint);
+    }
+
+    let roots: Vec<&str> = robot
+        .links
+        .iter()
+        .map(|link| link.name.as_str())
+        .filter(|link| !parent_joints.contains_key(link))
+        .collect();
+
+    let mut text = String::new();
+    let mut visited = HashSet::new();
+    for &root in &roots {
+        visited.insert(root);
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(root);
+        write_link_subtree(&mut text, root, 1, &child_joints, &mut visited);
+    }
+
+    // Every link below a root gets visited, so the rest hang off a cycle.
+    let unreached = robot
+        .links
+        .iter()
+        .map(|link| link.name.as_str())
+        .filter(|link| !visited.contains(link))
+        .collect();
+
+    KinematicTree {
+        text,
+        roots,
+        shared_children,
+        unreached,
+    }
+}
+
+/// Logs the link tree and warns about structure that doesn't form a single
+/// tree. Joint cycles were already rejected by `validate_joint_links`.
+fn log_kinematic_tree(robot: &urdf_rs::Robot, parent_joints: &HashMap<&str, &urdf_rs::Joint>) {
+    let tree = kinematic_tree(robot, parent_joints);
+
+    for link in &tree.shared_children {
+        warn!(
+            "Link `{link}` of robot `{}` is the child of several joints",
+            robot.name
+        );
+    }
+    if tree.roots.len() > 1 {
+        warn!(
+            "Robot `{}` has {} root links: {}",
+            robot.name,
+            tree.roots.len(),
+            tree.roots.join(", ")
+        );
+    }
+    info!("Kinematic tree of robot `{}`:\n{}", robot.name, tree.text);
+}
+
+fn write_link_subtree<'a>(
+    tree: &mut String,
+    link: &str,
+    depth: usize,
+    child_joints: &HashMap<&str, Vec<&'a urdf_rs::Joint>>,
+    visited: &mut HashSet<&'a str>,
+) {
+    for joint in child_joints.get(link).into_iter().flatten() {
+        let child = joint.child.link.as_str();
+        if !visited.insert(child) {
+            continue;
+        }
+
+        let axis = joint.axis.xyz;
+        let motion = match joint.joint_type {
+            JointType::Fixed => "Fixed".to_string(),
+            ref joint_type => {
+                format!("{joint_type:?}, axis [{} {} {}]", axis[0], axis[1], axis[2])
+            }
+        };
+        tree.push_str(&format!(
+            "\n{}{child} <- {} ({motion})",
+            "  ".repeat(depth),
+            joint.name
+        ));
+        write_link_subtree(tree, child, depth + 1, child_joints, visited);
+    }
+}
+
+/// Pose of `link` relative to the robot root with every joint at zero.
+fn root_frame_transform(link: &str, parent_joints: &HashMap<&str, &urdf_rs::Joint>) -> Transform {
+    let mut transform = Transform::IDENTITY;
+    let mut link = link;
+    // Bounded so a malformed URDF with a joint cycle can't hang the spawn.
+    for _ in 0..=parent_joints.len() {
+        let Some(joint) = parent_joints.get(link) else {
+            break;
+        };
+        transform = pose_to_transform(&joint.origin) * transform;
+        link = &joint.parent.link;
+    }
+    transform
+}
+
+fn validate_joint_links(robot: &urdf_rs::Robot) -> Result<(), SpawnRobotError> {
+    let link_names: HashSet<&str> = robot.links.iter().map(|link| link.name.as_str()).collect();
+
+    for joint in &robot.joints {
+        if !link_names.contains(joint.parent.link.as_str()) {
+            return Err(SpawnRobotError::MissingParentLink(
+                joint.parent.link.clone(),
+            ));
+        }
+        if !link_names.contains(joint.child.link.as_str()) {
+            return Err(SpawnRobotError::MissingChildLink(joint.child.link.clone()));
+        }
+    }
+
+    // Spawning parents each link to the one above it, so links on a cycle
+    // would form a `Parent` loop detached from the root.
+    let parent_joints = robot
+        .joints
+        .iter()
+        .map(|joint| (joint.child.link.as_str(), joint))
+        .collect();
+    let unreached = kinematic_tree(robot, &parent_joints).unreached;
+    if !unreached.is_empty() {
+        return Err(SpawnRobotError::JointCycle(
+            unreached.into_iter().map(str::to_string).collect(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Mesh file extensions with a registered `Mesh` asset loader.
+const MESH_EXTENSIONS: &[&str] = &["stl"];
+
+fn mesh_extension(filename: &str) -> String {
+    Path::new(filename)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn is_supported_mesh(filename: &str) -> bool {
+    MESH_EXTENSIONS.contains(&mesh_extension(filename).as_str())
+}
+
+/// Rejects collision meshes that can't be loaded, since the link would then
+/// look collision-free. Unsupported visual meshes are only skipped, with a
+/// warning, when the visuals are built.
+fn validate_geometry(robot: &urdf_rs::Robot) -> Result<(), SpawnRobotError> {
+    let geometries = robot
+        .links
+        .iter()
+        .flat_map(|link| &link.collision)
+        .map(|collision| &collision.geometry);
+
+    for geometry in geometries {
+        if let Geometry::Mesh { filename, .. } = geometry {
+            if !is_supported_mesh(filename) {
+                return Err(SpawnRobotError::UnsupportedGeometry(filename.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The assets robot parts are built from, with the cache sharing them
+/// between robots.
+#[derive(SystemParam)]
+struct RobotAssets<'w> {
+    asset_server: Res<'w, AssetServer>,
+    resolver: Res<'w, PackagePathResolver>,
+    cache: ResMut<'w, RobotAssetCache>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+}
+
+impl RobotAssets<'_> {
+    fn mesh(&mut self, geometry: &Geometry, base_dir: &Path) -> Option<Handle<Mesh>> {
+        self.cache.mesh(
+            geometry,
+            base_dir,
+            &self.resolver,
+            &self.asset_server,
+            &mut self.meshes,
+        )
+    }
+}
+
+fn process_urdf_visuals(
+    mut commands: Commands,
+    query: Query<(Entity, &UrdfVisual), Added<UrdfVisual>>,
+    mut assets: RobotAssets,
+    color_overrides: Res<LinkColorOverrides>,
+    link_materials: Res<LinkMaterials>,
+    render_mode: Res<RenderMode>,
+    hidden_links: Res<HiddenLinks>,
+) {
+    for (entity, urdf_visual) in query.iter() {
+        let Some(mesh_handle) = assets.mesh(&urdf_visual.geometry, &urdf_visual.base_dir) else {
+            continue;
+        };
+        let material_handle = create_material(
+            urdf_visual,
+            &color_overrides,
+            &link_materials,
+            &assets.asset_server,
+            &mut assets.cache,
+            &mut assets.materials,
+        );
+
+        let transform = urdf_to_transform(&urdf_visual.origin, &urdf_visual.geometry);
+
+        commands.entity(entity).insert(PbrBundle {
+            mesh: mesh_handle,
+            material: material_handle,
+            transform,
+            visibility: visual_visibility(urdf_visual, *render_mode, &hidden_links),
+            ..Default::default()
+        });
+    }
+}
+
+fn process_urdf_collisions(
+    mut commands: Commands,
+    query: Query<(Entity, &UrdfCollision), Added<UrdfCollision>>,
+    mut assets: RobotAssets,
+    render_mode: Res<RenderMode>,
+    colors: Res<CollisionColors>,
+    spawn_indices: Query<&SpawnIndex>,
+    mut robot_materials: ResMut<CollisionMaterials>,
+) {
+    for (entity, urdf_collision) in query.iter() {
+        let index = spawn_indices
+            .get(urdf_collision.robot)
+            .map_or(0, |index| index.0)
+            % colors.0.len().max(1);
+        let material = robot_materials
+            .0
+            .entry(index)
+            .or_insert_with(|| {
+                let color = colors
+                    .0
+                    .get(index)
+                    .copied()
+                    .unwrap_or(Color::srgba(1.0, 0.5, 0.0, 0.4));
+                assets.materials.add(StandardMaterial {
+                    base_color: color,
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..Default::default()
+                })
+            })
+            .clone();
+
+        let Some(mesh_handle) = assets.mesh(&urdf_collision.geometry, &urdf_collision.base_dir)
+        else {
+            continue;
+        };
+
+        let transform = urdf_to_transform(&urdf_collision.origin, &urdf_collision.geometry);
+
+        commands.entity(entity).insert(PbrBundle {
+            mesh: mesh_handle,
+            material,
+            transform,
+            visibility: render_mode.collision_visibility(),
+            ..Default::default()
+        });
+    }
+}
+
+/// Mesh of a URDF primitive shape. Mesh files are loaded by the asset
+/// server instead.
+fn primitive_mesh(geometry: &Geometry) -> Option<Mesh> {
+    Some(match geometry {
+        Geometry::Mesh { .. } => return None,
+        Geometry::Box { size } => {
+            Mesh::from(Cuboid::new(size[0] as f32, size[1] as f32, size[2] as f32))
+        }
+        Geometry::Cylinder { radius, length } => {
+            Mesh::from(Cylinder::new(*radius as f32, *length as f32))
+        }
+        Geometry::Capsule { radius, length } => {
+            Mesh::from(Capsule3d::new(*radius as f32, *length as f32))
+        }
+        Geometry::Sphere { radius } => Mesh::from(Sphere {
+            radius: *radius as f32,
+        }),
+    })
+}
+
+fn create_material(
+    urdf_visual: &UrdfVisual,
+    color_overrides: &LinkColorOverrides,
+    link_materials: &LinkMaterials,
+    asset_server: &AssetServer,
+    cache: &mut RobotAssetCache,
+    materials: &mut Assets<StandardMaterial>,
+) -> Handle<StandardMaterial> {
+    let texture = urdf_visual
+        .material
+        .as_ref()
+        .and_then(|material| material.texture.as_ref())
+        .map(|texture| urdf_visual.base_dir.join(&texture.filename));
+
+    cache.material(
+        visual_color(urdf_visual, color_overrides),
+        texture,
+        link_materials.get(&urdf_visual.link_name),
+        asset_server,
+        materials,
+    )
+}
+
+fn visual_color(urdf_visual: &UrdfVisual, color_overrides: &LinkColorOverrides) -> Color {
+    if let Some(color) = color_overrides.0.get(&urdf_visual.link_name) {
+        return *color;
+    }
+
+    let Some(material) = &urdf_visual.material else {
+        return Color::srgba(0.8, 0.8, 0.8, 1.0);
+    };
+
+    if let Some(urdf_color) = &material.color {
+        Color::srgba(
+            urdf_color.rgba[0] as f32,
+            urdf_color.rgba[1] as f32,
+            urdf_color.rgba[2] as f32,
+            urdf_color.rgba[3] as f32,
+        )
+    } else if material.texture.is_some() {
+        Color::WHITE
+    } else {
+        Color::srgba(0.8, 0.8, 0.8, 1.0)
+    }
+}
+
+// Materials are shared between visuals, so swap handles rather than editing
+// the material in place.
+fn refresh_link_materials(
+    mut commands: Commands,
+    color_overrides: Res<LinkColorOverrides>,
+    link_materials: Res<LinkMaterials>,
+    visuals: Query<(Entity, &UrdfVisual), With<Handle<StandardMaterial>>>,
+    asset_server: Res<AssetServer>,
+    mut cache: ResMut<RobotAssetCache>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, urdf_visual) in visuals.iter() {
+        let material = create_material(
+            urdf_visual,
+            &color_overrides,
+            &link_materials,
+            &asset_server,
+            &mut cache,
+            &mut materials,
+        );
+        commands.entity(entity).insert(material);
+    }
+}
+
+fn urdf_to_transform(origin: &Pose, geometry: &Geometry) -> Transform {
+    let mut transform = pose_to_transform(origin);
+
+    if let Geometry::Mesh {
+        scale: Some(mesh_scale),
+        ..
+    } = geometry
+    {
+        transform.scale = Vec3::new(
+            mesh_scale[0] as f32,
+            mesh_scale[1] as f32,
+            mesh_scale[2] as f32,
+        );
+    }
+
+    transform
+}
+
+fn pose_to_transform(pose: &Pose) -> Transform {
+    let pos = pose.xyz;
+    let rot = pose.rpy;
+
+    Transform {
+        translation: Vec3::new(pos[0] as f32, pos[1] as f32, pos[2] as f32),
+        rotation: Quat::from_euler(EulerRot::XYZ, rot[0] as f32, rot[1] as f32, rot[2] as f32),
+        scale: Vec3::ONE,
+    }
+}
+
+fn reset_to_initial_pose(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&InitialPose, &mut Transform)>,
+    mut joints: Query<&mut RobotJoint>,
+) {
+    if !keys.just_pressed(KeyCode::KeyR) {
+        return;
+    }
+
+    for (initial_pose, mut transform) in query.iter_mut() {
+        *transform = initial_pose.0;
+    }
+    for mut joint in joints.iter_mut() {
+        joint.position = 0.0;
+    }
+}
+
+fn apply_mimic_joints(mimics: Query<(Entity, &MimicJoint)>, mut joints: Query<&mut RobotJoint>) {
+    for (entity, mimic) in mimics.iter() {
+        let Ok(source) = joints.get(mimic.source) else {
+            continue;
+        };
+        let position = mimic.multiplier * source.position + mimic.offset;
+        let Ok(mut joint) = joints.get_mut(entity) else {
+            continue;
+        };
+        // Only touch the joint when it moves, so `Changed` stays meaningful.
+        if joint.position != position {
+            joint.position = position;
+        }
+    }
+}
+
+fn apply_joint_positions(
+    mut joints: Query<(&RobotJoint, &InitialPose, &mut Transform), Changed<RobotJoint>>,
+) {
+    for (joint, initial_pose, mut transform) in joints.iter_mut() {
+        if let Some(moved) = joint.link_transform(&initial_pose.0, joint.position) {
+            *transform = moved;
+        }
+    }
+}
+
+fn read_joint_states(
+    time: Res<Time>,
+    mut joints: Query<(&RobotJoint, &InitialPose, &Transform, &mut JointState)>,
+) {
+    let delta = time.delta_seconds();
+    for (joint, initial_pose, transform, mut state) in joints.iter_mut() {
+        let Some(axis) = joint.axis.try_normalize() else {
+            continue;
+        };
+        let initial = initial_pose.0;
+        let position = match joint.joint_type {
+            JointType::Revolute => {
+                joint_angle(initial.rotation.inverse() * transform.rotation, axis)
+            }
+            JointType::Continuous => unwrap_angle(
+                state.position,
+                joint_angle(initial.rotation.inverse() * transform.rotation, axis),
+            ),
+            JointType::Prismatic => (initial.rotation.inverse()
+                * (transform.translation - initial.translation))
+                .dot(axis),
+            _ => continue,
+        };
+
+        let velocity = if delta > 0.0 {
+            (position - state.position) / delta
+        } else {
+            0.0
+        };
+        *state = JointState { position, velocity };
+    }
+}
+
+fn cycle_render_mode(keys: Res<ButtonInput<KeyCode>>, mut render_mode: ResMut<RenderMode>) {
+    if keys.just_pressed(KeyCode::KeyV) {
+        *render_mode = match *render_mode {
+            RenderMode::Visual => RenderMode::Collision,
+            RenderMode::Collision => RenderMode::Both,
+            RenderMode::Both => RenderMode::Visual,
+        };
+        info!("Render mode: {:?}", *render_mode);
+    }
+}
+
+fn apply_render_mode(
+    render_mode: Res<RenderMode>,
+    hidden_links: Res<HiddenLinks>,
+    mut visuals: Query<(&UrdfVisual, &mut Visibility), Without<UrdfCollision>>,
+    mut collisions: Query<&mut Visibility, (With<UrdfCollision>, Without<UrdfVisual>)>,
+) {
+    if !render_mode.is_changed() && !hidden_links.is_changed() {
+        return;
+    }
+
+    for (urdf_visual, mut visibility) in visuals.iter_mut() {
+        *visibility = visual_visibility(urdf_visual, *render_mode, &hidden_links);
+    }
+    if render_mode.is_changed() {
+        for mut visibility in collisions.iter_mut() {
+            *visibility = render_mode.collision_visibility();
+        }
+    }
+}
+
+/// World-space box around a robot's visual meshes, measured once they have
+/// all loaded.
+#[derive(Component)]
+pub struct RobotBounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl RobotBounds {
+    /// The box around `aabb` once placed by `transform`, merged into `bounds`
+    /// if there is one.
+    fn merge(bounds: Option<Self>, aabb: &Aabb, transform: &GlobalTransform) -> Self {
+        let mut merged = bounds.unwrap_or(RobotBounds {
+            min: Vec3::INFINITY,
+            max: Vec3::NEG_INFINITY,
+        });
+        let center = Vec3::from(aabb.center);
+        let half_extents = Vec3::from(aabb.half_extents);
+        for corner in 0..8 {
+            let sign = Vec3::new(
+                if corner & 1 == 0 { -1.0 } else { 1.0 },
+                if corner & 2 == 0 { -1.0 } else { 1.0 },
+                if corner & 4 == 0 { -1.0 } else { 1.0 },
+            );
+            let point = transform.transform_point(center + sign * half_extents);
+            merged.min = merged.min.min(point);
+            merged.max = merged.max.max(point);
+        }
+        merged
+    }
+
+    pub fn intersects(&self, other: &RobotBounds) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+}
+
+/// Checks each robot once its `RobotBounds` are known, warning when it
+/// reaches below the floor at y = 0 or overlaps another robot. With
+/// `lift_above_floor`, robots reaching below the floor are raised onto it
+/// instead, and R resets them to the raised pose.
+#[derive(Resource, Default)]
+pub struct SpawnOverlapCheck {
+    pub lift_above_floor: bool,
+}
+
+/// Robots larger than this are probably authored in millimetres.
+const UNIT_MISMATCH_SIZE: f32 = 10.0;
+
+/// Logs each robot's overall size once all of its visual meshes have loaded,
+/// from the mesh bounds placed by the links' global transforms.
+fn report_robot_bounds(
+    mut commands: Commands,
+    robots: Query<Entity, (With<Robot>, Without<RobotBounds>)>,
+    children: Query<&Children>,
+    visuals: Query<(&Handle<Mesh>, &GlobalTransform), With<UrdfVisual>>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    'robots: for robot in robots.iter() {
+        let mut bounds = None;
+
+        for entity in children.iter_descendants(robot) {
+            let Ok((handle, transform)) = visuals.get(entity) else {
+                continue;
+            };
+            let Some(mesh) = meshes.get(handle) else {
+                continue 'robots;
+            };
+            if let Some(aabb) = mesh.compute_aabb() {
+                bounds = Some(RobotBounds::merge(bounds, &aabb, transform));
+            }
+        }
+
+        // Visuals get their meshes a frame after spawning.
+        let Some(bounds) = bounds else {
+            continue;
+        };
+
+        let size = bounds.max - bounds.min;
+        info!("Robot {robot:?} spans {size:.3} m (x, y, z)");
+        if size.max_element() > UNIT_MISMATCH_SIZE {
+            warn!(
+                "Robot {robot:?} is {:.1} m across, its URDF may be in millimetres rather than metres",
+                size.max_element()
+            );
+        }
+        commands.entity(robot).insert(bounds);
+    }
+}
+
+fn check_spawn_overlaps(
+    check: Res<SpawnOverlapCheck>,
+    mut robots: Query<(Entity, &mut Transform, &mut InitialPose, &mut RobotBounds), With<Robot>>,
+) {
+    let added: Vec<Entity> = robots
+        .iter_mut()
+        .filter(|(_, _, _, bounds)| bounds.is_added())
+        .map(|(robot, ..)| robot)
+        .collect();
+
+    for &robot in &added {
+        let Ok((_, mut transform, mut initial_pose, mut bounds)) = robots.get_mut(robot) else {
+            continue;
+        };
+        let depth = -bounds.min.y;
+        if depth <= 0.0 {
+            continue;
+        }
+
+        if check.lift_above_floor {
+            transform.translation.y += depth;
+            initial_pose.0.translation.y += depth;
+            bounds.min.y += depth;
+            bounds.max.y += depth;
+            info!("Lifted robot {robot:?} by {depth:.3} m onto the floor");
+        } else {
+            warn!("Robot {robot:?} reaches {depth:.3} m below the floor");
+        }
+    }
+
+    for &robot in &added {
+        let Ok((_, _, _, bounds)) = robots.get(robot) else {
+            continue;
+        };
+        for (other, _, _, other_bounds) in robots.iter() {
+            // Report each newly bounded pair once.
+            if other == robot || (other < robot && added.contains(&other)) {
+                continue;
+            }
+            if bounds.intersects(other_bounds) {
+                warn!("Robot {robot:?} overlaps robot {other:?}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, TAU};
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::ecs::world::CommandQueue;
+
+    use super::*;
+
+    const TWO_LINK_URDF: &str = r#"
+        <robot name="arm">
+          <link name="base"/>
+          <link name="forearm"/>
+          <joint name="elbow" type="revolute">
+            <parent link="base"/>
+            <child link="forearm"/>
+            <origin xyz="0 0 1"/>
+            <axis xyz="0 1 0"/>
+            <limit lower="-1" upper="1" effort="1" velocity="1"/>
+          </joint>
+        </robot>
+    "#;
+
+    fn spawn_str(world: &mut World, urdf: &str) -> Result<RobotHandle, SpawnRobotError> {
+        spawn_str_in(world, urdf, Path::new(""))
+    }
+
+    fn spawn_str_in(
+        world: &mut World,
+        urdf: &str,
+        base_dir: &Path,
+    ) -> Result<RobotHandle, SpawnRobotError> {
+        let mut queue = CommandQueue::default();
+        let handle = spawn_robot_from_str(
+            &mut Commands::new(&mut queue, world),
+            urdf,
+            base_dir,
+            Transform::IDENTITY,
+            InvalidInertia::Warn,
+            &MassOverrides::default(),
+        );
+        queue.apply(world);
+        handle
+    }
+
+    #[test]
+    fn mass_override_changes_total() {
+        let urdf = TWO_LINK_URDF
+            .replace(
+                r#"<link name="base"/>"#,
+                r#"<link name="base">
+                     <inertial>
+                       <mass value="2"/>
+                       <inertia ixx="0.1" iyy="0.1" izz="0.1" ixy="0" ixz="0" iyz="0"/>
+                     </inertial>
+                   </link>"#,
+            )
+            .replace(
+                r#"<link name="forearm"/>"#,
+                r#"<link name="forearm">
+                     <inertial>
+                       <mass value="1"/>
+                       <inertia ixx="0.01" iyy="0.02" izz="0.03" ixy="0" ixz="0" iyz="0"/>
+                     </inertial>
+                   </link>"#,
+            );
+        let mut robot = urdf_rs::read_from_string(&urdf).unwrap();
+        MassOverrides(HashMap::from([("forearm".to_string(), 4.0)])).apply(&mut robot);
+        let parent_joints: HashMap<&str, &urdf_rs::Joint> = robot
+            .joints
+            .iter()
+            .map(|joint| (joint.child.link.as_str(), joint))
+            .collect();
+
+        let (total_mass, center) = mass_properties(&robot, &parent_joints).unwrap();
+        assert_eq!(total_mass, 6.0);
+        // The forearm sits 1 m above the base and now carries 4 of 6 kg.
+        assert!(center.abs_diff_eq(Vec3::new(0.0, 0.0, 4.0 / 6.0), 1e-6));
+
+        let forearm = &robot.links[1].inertial.inertia;
+        assert!((forearm.ixx - 0.04).abs() < 1e-9);
+        assert!((forearm.izz - 0.12).abs() < 1e-9);
+    }
+
+    /// The plugins headless mode runs, plus `RobotPlugin`.
+    fn robot_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .add_plugins(RobotPlugin);
+        app
+    }
+
+    /// The part entities below `link` with a `T`, such as `UrdfVisual`.
+    fn parts_of<T: Component>(world: &World, link: Entity) -> Vec<Entity> {
+        world
+            .get::<Children>(link)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|&part| world.get::<T>(part).is_some())
+            .collect()
+    }
+
+    /// `TWO_LINK_URDF` with a red box visual and a collision box on each link.
+    fn boxed_urdf() -> String {
+        let link = |name: &str| {
+            format!(
+                r#"<link name="{name}">
+                     <visual>
+                       <geometry><box size="0.1 0.1 0.5"/></geometry>
+                       <material name="red"><color rgba="1 0 0 1"/></material>
+                     </visual>
+                     <collision><geometry><box size="0.1 0.1 0.5"/></geometry></collision>
+                   </link>"#
+            )
+        };
+        TWO_LINK_URDF
+            .replace(r#"<link name="base"/>"#, &link("base"))
+            .replace(r#"<link name="forearm"/>"#, &link("forearm"))
+    }
+
+    #[test]
+    fn copies_share_meshes_and_materials() {
+        let mut app = robot_app();
+        spawn_str(app.world_mut(), &boxed_urdf()).unwrap();
+        app.update();
+        let counts = |world: &World| {
+            (
+                world.resource::<Assets<Mesh>>().len(),
+                world.resource::<Assets<StandardMaterial>>().len(),
+            )
+        };
+        // One box mesh, the red material and the collision overlay.
+        assert_eq!(counts(app.world()), (1, 2));
+
+        spawn_str(app.world_mut(), &boxed_urdf()).unwrap();
+        app.update();
+        assert_eq!(counts(app.world()), (1, 2));
+    }
+
+    #[test]
+    fn despawn_removes_every_robot_entity() {
+        let mut world = World::new();
+        let robot = spawn_str(&mut world, &boxed_urdf()).unwrap();
+        // The root, two links and a visual and a collision part per link.
+        assert_eq!(world.iter_entities().count(), 7);
+
+        let mut queue = CommandQueue::default();
+        despawn_robot(&mut Commands::new(&mut queue, &world), robot);
+        queue.apply(&mut world);
+        assert_eq!(world.iter_entities().count(), 0);
+    }
+
+    #[test]
+    fn collision_mode_swaps_visuals_for_collisions() {
+        let mut app = robot_app();
+        let robot = spawn_str(app.world_mut(), &boxed_urdf()).unwrap();
+        app.update();
+        *app.world_mut().resource_mut::<RenderMode>() = RenderMode::Collision;
+        app.update();
+
+        let world = app.world();
+        for link in robot.links.values() {
+            for visual in parts_of::<UrdfVisual>(world, *link) {
+                assert_eq!(world.get::<Visibility>(visual), Some(&Visibility::Hidden));
+            }
+            for collision in parts_of::<UrdfCollision>(world, *link) {
+                assert_eq!(
+                    world.get::<Visibility>(collision),
+                    Some(&Visibility::Inherited)
+                );
+            }
+        }
+    }
+
+    fn sphere_visual(link_name: &str, material: Option<urdf_rs::Material>) -> UrdfVisual {
+        UrdfVisual {
+            geometry: Geometry::Sphere { radius: 0.1 },
+            material,
+            origin: Pose::default(),
+            base_dir: PathBuf::new(),
+            link_name: link_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn hidden_links_stay_hidden_in_every_mode() {
+        let hidden_links = HiddenLinks(HashSet::from(["gripper".to_string()]));
+        let gripper = sphere_visual("gripper", None);
+        let forearm = sphere_visual("forearm", None);
+
+        for mode in [RenderMode::Visual, RenderMode::Collision, RenderMode::Both] {
+            assert_eq!(
+                visual_visibility(&gripper, mode, &hidden_links),
+                Visibility::Hidden
+            );
+            assert_eq!(
+                visual_visibility(&forearm, mode, &hidden_links),
+                mode.visual_visibility()
+            );
+        }
+    }
+
+    #[test]
+    fn robots_get_distinct_collision_colors() {
+        let mut app = robot_app();
+        let robots: Vec<RobotHandle> = (0..2)
+            .map(|index| {
+                let robot = spawn_str(app.world_mut(), &boxed_urdf()).unwrap();
+                app.world_mut()
+                    .entity_mut(robot.root)
+                    .insert(SpawnIndex(index));
+                robot
+            })
+            .collect();
+        app.update();
+
+        let world = app.world();
+        let colors: Vec<Color> = robots
+            .iter()
+            .map(|robot| {
+                let [collision] = parts_of::<UrdfCollision>(world, robot.links["base"])[..] else {
+                    panic!("base should have one collision");
+                };
+                let material = world.get::<Handle<StandardMaterial>>(collision).unwrap();
+                world
+                    .resource::<Assets<StandardMaterial>>()
+                    .get(material)
+                    .unwrap()
+                    .base_color
+            })
+            .collect();
+        let expected = &world.resource::<CollisionColors>().0;
+        assert_eq!(colors, expected[..2]);
+        assert_ne!(colors[0], colors[1]);
+    }
+
+    #[test]
+    fn color_override_beats_urdf_material() {
+        let red = urdf_rs::Material {
+            name: "red".to_string(),
+            color: Some(urdf_rs::Color {
+                rgba: urdf_rs::Vec4([1.0, 0.0, 0.0, 1.0]),
+            }),
+            texture: None,
+        };
+        let gripper = sphere_visual("gripper", Some(red));
+        let overrides = LinkColorOverrides(HashMap::from([(
+            "gripper".to_string(),
+            Color::srgb(0.0, 0.0, 1.0),
+        )]));
+
+        assert_eq!(
+            visual_color(&gripper, &LinkColorOverrides::default()),
+            Color::srgba(1.0, 0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            visual_color(&gripper, &overrides),
+            Color::srgb(0.0, 0.0, 1.0)
+        );
+    }
+
+    /// Material of the one visual below `link`.
+    fn visual_material(world: &World, link: Entity) -> &StandardMaterial {
+        let [visual] = parts_of::<UrdfVisual>(world, link)[..] else {
+            panic!("link should have one visual");
+        };
+        let material = world.get::<Handle<StandardMaterial>>(visual).unwrap();
+        world
+            .resource::<Assets<StandardMaterial>>()
+            .get(material)
+            .unwrap()
+    }
+
+    #[test]
+    fn link_materials_reach_standard_material() {
+        let mut app = robot_app();
+        app.insert_resource(LinkMaterials {
+            default: MaterialConfig {
+                metallic: 0.8,
+                roughness: 0.2,
+            },
+            overrides: HashMap::from([(
+                "forearm".to_string(),
+                MaterialConfig {
+                    metallic: 0.1,
+                    roughness: 0.9,
+                },
+            )]),
+        });
+        let robot = spawn_str(app.world_mut(), &boxed_urdf()).unwrap();
+        app.update();
+
+        let base = visual_material(app.world(), robot.links["base"]);
+        assert_eq!((base.metallic, base.perceptual_roughness), (0.8, 0.2));
+        let forearm = visual_material(app.world(), robot.links["forearm"]);
+        assert_eq!((forearm.metallic, forearm.perceptual_roughness), (0.1, 0.9));
+    }
+
+    #[test]
+    fn textured_material_loads_texture() {
+        let urdf = boxed_urdf().replacen(
+            r#"<color rgba="1 0 0 1"/>"#,
+            r#"<texture filename="textures/checker.png"/>"#,
+            1,
+        );
+        let mut app = robot_app();
+        let robot = spawn_str_in(app.world_mut(), &urdf, Path::new("/opt/robots/arm")).unwrap();
+        app.update();
+
+        let base = visual_material(app.world(), robot.links["base"]);
+        assert_eq!(base.base_color, Color::WHITE);
+        let texture = base.base_color_texture.as_ref().unwrap();
+        assert_eq!(
+            texture.path().map(|path| path.path()),
+            Some(Path::new("/opt/robots/arm/textures/checker.png"))
+        );
+        let forearm = visual_material(app.world(), robot.links["forearm"]);
+        assert!(forearm.base_color_texture.is_none());
+    }
+
+    #[test]
+    fn spawns_capsule_mesh() {
+        let urdf = TWO_LINK_URDF.replace(
+            r#"<link name="forearm"/>"#,
+            r#"<link name="forearm">
+                 <visual><geometry><capsule radius="0.05" length="0.3"/></geometry></visual>
+               </link>"#,
+        );
+        let mut app = robot_app();
+        let robot = spawn_str(app.world_mut(), &urdf).unwrap();
+        app.update();
+
+        let world = app.world();
+        let [forearm] = parts_of::<UrdfVisual>(world, robot.links["forearm"])[..] else {
+            panic!("forearm should have one visual");
+        };
+        let mesh = world.get::<Handle<Mesh>>(forearm).unwrap();
+        let aabb = world
+            .resource::<Assets<Mesh>>()
+            .get(mesh)
+            .unwrap()
+            .compute_aabb()
+            .unwrap();
+        // The URDF length is the cylinder between the caps, as for cylinders.
+        assert!(Vec3::from(aabb.half_extents).abs_diff_eq(Vec3::new(0.05, 0.2, 0.05), 1e-5));
+    }
+
+    #[test]
+    fn merges_placed_boxes() {
+        let aabb = Aabb::from_min_max(Vec3::new(-0.5, -1.0, -1.5), Vec3::new(0.5, 1.0, 1.5));
+        // Turned a quarter about Y, the 1 x 2 x 3 box becomes 3 x 2 x 1.
+        let turned = GlobalTransform::from(
+            Transform::from_xyz(2.0, 1.0, 0.0).with_rotation(Quat::from_rotation_y(FRAC_PI_2)),
+        );
+        let bounds = RobotBounds::merge(None, &aabb, &turned);
+        assert!(bounds.min.abs_diff_eq(Vec3::new(0.5, 0.0, -0.5), 1e-5));
+        assert!(bounds.max.abs_diff_eq(Vec3::new(3.5, 2.0, 0.5), 1e-5));
+
+        let bounds = RobotBounds::merge(Some(bounds), &aabb, &GlobalTransform::IDENTITY);
+        assert!(bounds.min.abs_diff_eq(Vec3::new(-0.5, -1.0, -1.5), 1e-5));
+        assert!(bounds.max.abs_diff_eq(Vec3::new(3.5, 2.0, 1.5), 1e-5));
+    }
+
+    #[test]
+    fn skips_unsupported_visual_mesh() {
+        let urdf = TWO_LINK_URDF
+            .replace(
+                r#"<link name="base"/>"#,
+                r#"<link name="base">
+                     <visual><geometry><box size="0.1 0.1 0.1"/></geometry></visual>
+                   </link>"#,
+            )
+            .replace(
+                r#"<link name="forearm"/>"#,
+                r#"<link name="forearm">
+                     <visual><geometry><mesh filename="forearm.dae"/></geometry></visual>
+                   </link>"#,
+            );
+        let mut app = robot_app();
+        let robot = spawn_str(app.world_mut(), &urdf).unwrap();
+        app.update();
+
+        let world = app.world();
+        let [base] = parts_of::<UrdfVisual>(world, robot.links["base"])[..] else {
+            panic!("base should have one visual");
+        };
+        let [forearm] = parts_of::<UrdfVisual>(world, robot.links["forearm"])[..] else {
+            panic!("forearm should have one visual");
+        };
+        assert!(world.get::<Handle<Mesh>>(base).is_some());
+        assert!(world.get::<Handle<Mesh>>(forearm).is_none());
+    }
+
+    #[test]
+    fn rejects_dangling_parent_link() {
+        let urdf = TWO_LINK_URDF.replace(r#"<parent link="base"/>"#, r#"<parent link="torso"/>"#);
+        assert!(matches!(
+            spawn_str(&mut World::new(), &urdf),
+            Err(SpawnRobotError::MissingParentLink(link)) if link == "torso"
+        ));
+    }
+
+    #[test]
+    fn rejects_dangling_child_link() {
+        let urdf = TWO_LINK_URDF.replace(r#"<child link="forearm"/>"#, r#"<child link="hand"/>"#);
+        assert!(matches!(
+            spawn_str(&mut World::new(), &urdf),
+            Err(SpawnRobotError::MissingChildLink(link)) if link == "hand"
+        ));
+    }
+
+    #[test]
+    fn rejects_joint_cycle() {
+        let urdf = TWO_LINK_URDF.replace(
+            "</robot>",
+            r#"<link name="hand"/>
+               <joint name="wrist" type="fixed">
+                 <parent link="forearm"/><child link="hand"/>
+               </joint>
+               <joint name="loop" type="fixed">
+                 <parent link="hand"/><child link="forearm"/>
+               </joint>
+             </robot>"#,
+        );
+        let mut world = World::new();
+        assert!(matches!(
+            spawn_str(&mut world, &urdf),
+            Err(SpawnRobotError::JointCycle(links)) if links == ["forearm", "hand"]
+        ));
+        assert_eq!(world.entities().len(), 0);
+    }
+
+    #[test]
+    fn rejects_unsupported_collision_mesh() {
+        let urdf = TWO_LINK_URDF.replace(
+            r#"<link name="forearm"/>"#,
+            r#"<link name="forearm">
+                 <collision><geometry><mesh filename="forearm.dae"/></geometry></collision>
+               </link>"#,
+        );
+        assert!(matches!(
+            spawn_str(&mut World::new(), &urdf),
+            Err(SpawnRobotError::UnsupportedGeometry(filename)) if filename == "forearm.dae"
+        ));
+    }
+
+    const GRIPPER_URDF: &str = r#"
+        <robot name="gripper">
+          <link name="palm"/>
+          <link name="left_finger"/>
+          <link name="right_finger"/>
+          <joint name="left" type="prismatic">
+            <parent link="palm"/>
+            <child link="left_finger"/>
+            <axis xyz="1 0 0"/>
+            <limit lower="0" upper="0.05" effort="1" velocity="1"/>
+          </joint>
+          <joint name="right" type="prismatic">
+            <parent link="palm"/>
+            <child link="right_finger"/>
+            <axis xyz="1 0 0"/>
+            <limit lower="-0.05" upper="0" effort="1" velocity="1"/>
+            <mimic joint="left" multiplier="-1" offset="0.001"/>
+          </joint>
+        </robot>
+    "#;
+
+    #[test]
+    fn mimic_finger_follows_source() {
+        let mut world = World::new();
+        let gripper = spawn_str(&mut world, GRIPPER_URDF).unwrap();
+        let (left, right) = (gripper.links["left_finger"], gripper.links["right_finger"]);
+        let mimic = world.get::<MimicJoint>(right).unwrap();
+        assert_eq!((mimic.source, mimic.multiplier), (left, -1.0));
+
+        world.get_mut::<RobotJoint>(left).unwrap().position = 0.02;
+        world.run_system_once(apply_mimic_joints);
+        world.run_system_once(apply_joint_positions);
+
+        assert_eq!(world.get::<RobotJoint>(right).unwrap().position, -0.019);
+        let translation = |link| world.get::<Transform>(link).unwrap().translation;
+        assert_eq!(translation(left), Vec3::new(0.02, 0.0, 0.0));
+        assert_eq!(translation(right), Vec3::new(-0.019, 0.0, 0.0));
+    }
+
+    #[test]
+    fn spawns_prismatic_joint_with_urdf_axis_and_limits() {
+        let urdf = TWO_LINK_URDF
+            .replace(r#"type="revolute""#, r#"type="prismatic""#)
+            .replace(r#"<axis xyz="0 1 0"/>"#, r#"<axis xyz="1 0 0"/>"#)
+            .replace(r#"lower="-1" upper="1""#, r#"lower="0" upper="0.04""#);
+        let mut world = World::new();
+        let robot = spawn_str(&mut world, &urdf).unwrap();
+
+        let joint = world.get::<RobotJoint>(robot.links["forearm"]).unwrap();
+        assert_eq!(joint.joint_type, JointType::Prismatic);
+        assert_eq!(joint.axis, Vec3::X);
+        assert_eq!((joint.lower, joint.upper), (0.0, 0.04));
+        assert_eq!(joint.clamp_position(0.1), 0.04);
+    }
+
+    #[test]
+    fn joint_angle_of_known_rotations() {
+        let angle = |rotation, axis| joint_angle(rotation, axis);
+        assert!((angle(Quat::from_rotation_y(0.5), Vec3::Y) - 0.5).abs() < 1e-6);
+        assert!((angle(Quat::from_rotation_y(0.5), Vec3::NEG_Y) + 0.5).abs() < 1e-6);
+        assert!((angle(Quat::from_rotation_z(3.0), Vec3::Z) - 3.0).abs() < 1e-6);
+        assert!((angle(Quat::from_rotation_z(4.0), Vec3::Z) - (4.0 - TAU)).abs() < 1e-6);
+        // Unnormalized axes and rotations about other axes.
+        assert!((angle(Quat::from_rotation_x(-1.0), Vec3::new(2.0, 0.0, 0.0)) + 1.0).abs() < 1e-6);
+        assert_eq!(angle(Quat::from_rotation_x(1.0), Vec3::Y), 0.0);
+    }
+
+    #[test]
+    fn unwraps_angles_across_pi() {
+        // About four turns forward, then two back, in steps of 0.5 rad.
+        let turned: Vec<f32> = (0..=50)
+            .map(|step| step as f32 * 0.5)
+            .chain((0..=25).map(|step| 25.0 - step as f32 * 0.5))
+            .collect();
+        let mut position = 0.0;
+        for &angle in &turned {
+            let wrapped = joint_angle(Quat::from_rotation_z(angle), Vec3::Z);
+            position = unwrap_angle(position, wrapped);
+            assert!((position - angle).abs() < 1e-3, "{position} != {angle}");
+        }
+    }
+
+    #[test]
+    fn reads_back_joint_state() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let robot = spawn_str(&mut world, TWO_LINK_URDF).unwrap();
+        let forearm = robot.links["forearm"];
+
+        world.get_mut::<RobotJoint>(forearm).unwrap().position = 0.5;
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(250));
+        world.run_system_once(apply_joint_positions);
+        world.run_system_once(read_joint_states);
+
+        let state = world.get::<JointState>(forearm).unwrap();
+        assert!((state.position - 0.5).abs() < 1e-6);
+        assert!((state.velocity - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn spawns_inline_urdf() {
+        let mut world = World::new();
+        let robot = spawn_str(&mut world, TWO_LINK_URDF).unwrap();
+
+        assert_eq!(robot.links.len(), 2);
+        let mut names: Vec<String> = world
+            .query_filtered::<&Name, With<RobotLink>>()
+            .iter(&world)
+            .map(|name| name.to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["arm/base", "arm/forearm"]);
+        assert_eq!(world.get::<Name>(robot.root).unwrap().as_str(), "arm");
+        assert_eq!(
+            world
+                .get::<RobotJoint>(robot.links["forearm"])
+                .unwrap()
+                .name,
+            "arm/elbow"
+        );
+
+        let find = |name: &'static str| {
+            move |links: Query<(Entity, &Name), With<RobotLink>>| find_link_by_name(&links, name)
+        };
+        assert_eq!(
+            world.run_system_once(find("arm/forearm")),
+            Some(robot.links["forearm"])
+        );
+        assert_eq!(
+            world.run_system_once(find("base")),
+            Some(robot.links["base"])
+        );
+        assert_eq!(world.run_system_once(find("gripper")), None);
+    }
+
+    #[test]
+    fn up_axis_correction_maps_up_to_y() {
+        assert!((up_axis_correction(UpAxis::Z) * Vec3::Z).abs_diff_eq(Vec3::Y, 1e-6));
+        assert_eq!(up_axis_correction(UpAxis::Y), Quat::IDENTITY);
+    }
+
+    #[test]
+    fn lifted_pose_survives_reset() {
+        let mut world = World::new();
+        world.insert_resource(SpawnOverlapCheck {
+            lift_above_floor: true,
+        });
+        let start = Transform::from_xyz(1.0, 0.0, 2.0);
+        let robot = world
+            .spawn((
+                Robot,
+                start,
+                InitialPose(start),
+                RobotBounds {
+                    min: Vec3::new(0.5, -0.25, 1.5),
+                    max: Vec3::new(1.5, 1.0, 2.5),
+                },
+            ))
+            .id();
+
+        world.run_system_once(check_spawn_overlaps);
+        let lifted = Vec3::new(1.0, 0.25, 2.0);
+        assert_eq!(world.get::<Transform>(robot).unwrap().translation, lifted);
+        assert_eq!(world.get::<RobotBounds>(robot).unwrap().min.y, 0.0);
+
+        world.get_mut::<Transform>(robot).unwrap().translation = Vec3::splat(5.0);
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::KeyR);
+        world.insert_resource(keys);
+        world.run_system_once(reset_to_initial_pose);
+        assert_eq!(world.get::<Transform>(robot).unwrap().translation, lifted);
+    }
+
+    fn resolver() -> PackagePathResolver {
+        PackagePathResolver(HashMap::from([(
+            "low_cost_robot".to_string(),
+            PathBuf::from("/opt/robots/low_cost_robot"),
+        )]))
+    }
+
+    #[test]
+    fn resolves_known_package() {
+        assert_eq!(
+            resolver()
+                .resolve("package://low_cost_robot/meshes/base.stl")
+                .unwrap(),
+            PathBuf::from("/opt/robots/low_cost_robot/meshes/base.stl")
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_package() {
+        let err = resolver()
+            .resolve("package://other_robot/meshes/base.stl")
+            .unwrap_err();
+        assert_eq!(err.0, "other_robot");
+    }
+
+    #[test]
+    fn keeps_plain_relative_path() {
+        assert_eq!(
+            resolver().resolve("meshes/base.stl").unwrap(),
+            PathBuf::from("meshes/base.stl")
+        );
+    }
+
+    #[test]
+    fn loads_relative_mesh_from_urdf_dir() {
+        let urdf = TWO_LINK_URDF.replace(
+            r#"<link name="forearm"/>"#,
+            r#"<link name="forearm">
+                 <visual><geometry><mesh filename="meshes/forearm.stl"/></geometry></visual>
+               </link>"#,
+        );
+        let mut app = robot_app();
+        let robot = spawn_str_in(app.world_mut(), &urdf, Path::new("/opt/robots/arm")).unwrap();
+        app.update();
+
+        let world = app.world();
+        let [forearm] = parts_of::<UrdfVisual>(world, robot.links["forearm"])[..] else {
+            panic!("forearm should have one visual");
+        };
+        let mesh = world.get::<Handle<Mesh>>(forearm).unwrap();
+        assert_eq!(
+            mesh.path().map(|path| path.path()),
+            Some(Path::new("/opt/robots/arm/meshes/forearm.stl"))
+        );
+    }
+
+    fn tree_of(
+        links: &[&str],
+        joints: &[(&str, &str, &str)],
+    ) -> (String, Vec<String>, Vec<String>) {
+        let links: String = links
+            .iter()
+            .map(|link| format!(r#"<link name="{link}"/>"#))
+            .collect();
+        let joints: String = joints
+            .iter()
+            .map(|(joint, parent, child)| {
+                format!(
+                    r#"<joint name="{joint}" type="fixed">
+                         <parent link="{parent}"/><child link="{child}"/>
+                       </joint>"#
+                )
+            })
+            .collect();
+        let robot =
+            urdf_rs::read_from_string(&format!(r#"<robot name="r">{links}{joints}</robot>"#))
+                .unwrap();
+        let parent_joints = robot
+            .joints
+            .iter()
+            .map(|joint| (joint.child.link.as_str(), joint))
+            .collect();
+        let tree = kinematic_tree(&robot, &parent_joints);
+        let owned = |links: Vec<&str>| links.into_iter().map(str::to_string).collect();
+        (tree.text, owned(tree.roots), owned(tree.unreached))
+    }
+
+    #[test]
+    fn tree_of_chain() {
+        let (text, roots, unreached) =
+            tree_of(&["a", "b", "c"], &[("ab", "a", "b"), ("bc", "b", "c")]);
+        assert_eq!(text, "a\n  b <- ab (Fixed)\n    c <- bc (Fixed)");
+        assert_eq!(roots, ["a"]);
+        assert!(unreached.is_empty());
+    }
+
+    #[test]
+    fn tree_of_branch() {
+        let (text, _, unreached) = tree_of(&["a", "b", "c"], &[("ab", "a", "b"), ("ac", "a", "c")]);
+        assert_eq!(text, "a\n  b <- ab (Fixed)\n  c <- ac (Fixed)");
+        assert!(unreached.is_empty());
+    }
+
+    #[test]
+    fn tree_of_multiple_roots() {
+        let (text, roots, _) = tree_of(&["a", "b", "c"], &[("ab", "a", "b")]);
+        assert_eq!(text, "a\n  b <- ab (Fixed)\nc");
+        assert_eq!(roots, ["a", "c"]);
+    }
+
+    #[test]
+    fn tree_of_cycle() {
+        let (text, roots, unreached) =
+            tree_of(&["a", "b", "c"], &[("bc", "b", "c"), ("cb", "c", "b")]);
+        assert_eq!(text, "a");
+        assert_eq!(roots, ["a"]);
+        assert_eq!(unreached, ["b", "c"]);
+    }
+}
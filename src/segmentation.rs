@@ -0,0 +1,626 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::core::FrameCount;
+use bevy::core_pipeline::tonemapping::{DebandDither, Tonemapping};
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::graph::CameraDriverLabel;
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_graph::{
+    Node, NodeRunError, RenderGraph, RenderGraphContext, RenderLabel,
+};
+use bevy::render::render_resource::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, ImageCopyBuffer,
+    ImageDataLayout, Maintain, MapMode, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::renderer::{RenderContext, RenderDevice, RenderQueue};
+use bevy::render::texture::GpuImage;
+use bevy::render::view::RenderLayers;
+use bevy::render::{Extract, Render, RenderApp, RenderSet};
+
+use crate::camera::{MainCamera, ScreenshotSettings};
+use crate::robot::{RobotLink, UrdfVisual};
+use crate::world::WorldEnvironment;
+
+/// Segmentation view, toggled with L: the camera draws every robot link in a
+/// flat color unique to that link over a black background, so F12 saves a
+/// segmentation mask. Each link's id is logged as its meshes load.
+///
+/// With `SegmentationCameraConfig::size` set, a `SegmentationCamera` riding
+/// on the main camera also renders the mask offscreen every frame and copies
+/// it back to the CPU. F11 saves its latest frame.
+pub struct SegmentationPlugin;
+
+impl Plugin for SegmentationPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+
+        app.init_resource::<SegmentationView>()
+            .init_resource::<SegmentationIds>()
+            .init_resource::<SegmentationMaterials>()
+            .init_resource::<SegmentationCameraConfig>()
+            .insert_resource(SegmentationFrameReceiver(Mutex::new(receiver)))
+            .add_systems(
+                Update,
+                (
+                    prune_segmentation_links,
+                    spawn_segmentation_meshes,
+                    toggle_segmentation_view,
+                    apply_segmentation_view.run_if(resource_changed::<SegmentationView>),
+                    attach_segmentation_camera,
+                    receive_segmentation_frames,
+                    save_segmentation_frame,
+                )
+                    .chain(),
+            );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(SegmentationFrameSender(sender))
+            .init_resource::<SegmentationReadbacks>()
+            .add_systems(ExtractSchedule, extract_segmentation_readbacks)
+            .add_systems(
+                Render,
+                read_back_segmentation_frames.after(RenderSet::Render),
+            );
+        let mut graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        graph.add_node(SegmentationCopyLabel, SegmentationCopyNode);
+        graph.add_node_edge(CameraDriverLabel, SegmentationCopyLabel);
+    }
+}
+
+/// Render layer holding the flat-colored copies of the link meshes.
+const SEGMENTATION_LAYER: usize = 1;
+
+#[derive(Resource, Default)]
+pub struct SegmentationView(pub bool);
+
+/// Segmentation id of each link entity, numbered from 1 in the order their
+/// meshes loaded. Id 0 is the background. Despawned links give up their ids.
+#[derive(Resource, Default)]
+pub struct SegmentationIds(pub HashMap<Entity, u32>);
+
+/// Flat segmentation material of each link entity.
+#[derive(Resource, Default)]
+struct SegmentationMaterials(HashMap<Entity, Handle<StandardMaterial>>);
+
+/// The flat color links with segmentation id `id` are drawn in. Hues step by
+/// the golden angle, so consecutive ids stay far apart.
+pub fn segmentation_color(id: u32) -> Color {
+    if id == 0 {
+        return Color::BLACK;
+    }
+    Color::hsl((id as f32 * 137.508) % 360.0, 0.9, 0.5)
+}
+
+/// Gives every loaded visual mesh an unlit copy on `SEGMENTATION_LAYER`, in
+/// its link's segmentation color.
+fn spawn_segmentation_meshes(
+    mut commands: Commands,
+    meshes: Query<(Entity, &Handle<Mesh>), Added<Handle<Mesh>>>,
+    visuals: Query<&Parent, With<UrdfVisual>>,
+    names: Query<&Name>,
+    mut ids: ResMut<SegmentationIds>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut link_materials: ResMut<SegmentationMaterials>,
+) {
+    for (entity, mesh) in meshes.iter() {
+        let Ok(link) = visuals.get(entity) else {
+            continue;
+        };
+
+        let link = link.get();
+        let next_id = ids.0.values().max().map_or(1, |id| id + 1);
+        let id = *ids.0.entry(link).or_insert_with(|| {
+            if let Ok(name) = names.get(link) {
+                info!("Segmentation id {next_id}: {name}");
+            }
+            next_id
+        });
+        let material = link_materials
+            .0
+            .entry(link)
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: segmentation_color(id),
+                    unlit: true,
+                    ..default()
+                })
+            })
+            .clone();
+
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material,
+                    ..default()
+                },
+                RenderLayers::layer(SEGMENTATION_LAYER),
+                NotShadowCaster,
+            ));
+        });
+    }
+}
+
+/// Forgets links that were despawned, such as when robots are reloaded.
+fn prune_segmentation_links(
+    mut removed: RemovedComponents<RobotLink>,
+    mut ids: ResMut<SegmentationIds>,
+    mut link_materials: ResMut<SegmentationMaterials>,
+) {
+    for link in removed.read() {
+        ids.0.remove(&link);
+        link_materials.0.remove(&link);
+    }
+}
+
+/// Size in pixels of the offscreen segmentation camera, which is only
+/// spawned when this is set.
+#[derive(Resource, Default)]
+pub struct SegmentationCameraConfig {
+    pub size: Option<UVec2>,
+}
+
+/// Offscreen camera drawing the segmentation layer into `image`, like the L
+/// view. After each frame the image is copied into the camera's
+/// `SegmentationFrame`. MSAA blends the colors along link edges, so run with
+/// `--msaa 1` for exact ids.
+#[derive(Component)]
+pub struct SegmentationCamera {
+    pub image: Handle<Image>,
+}
+
+/// The latest image a `SegmentationCamera` rendered, as sRGB RGBA8 pixels
+/// row by row from the top left. Empty until the first frame arrives.
+#[derive(Component, Default)]
+pub struct SegmentationFrame {
+    pub size: UVec2,
+    pub data: Vec<u8>,
+}
+
+/// Buffer each frame of a segmentation camera is copied into, to be mapped
+/// for reading.
+#[derive(Component, Clone)]
+struct SegmentationReadback {
+    image: Handle<Image>,
+    buffer: Buffer,
+}
+
+#[derive(Resource)]
+struct SegmentationFrameReceiver(Mutex<Receiver<(Entity, Vec<u8>)>>);
+
+#[derive(Resource)]
+struct SegmentationFrameSender(Sender<(Entity, Vec<u8>)>);
+
+/// Segmentation cameras extracted into the render world this frame.
+#[derive(Resource, Default)]
+struct SegmentationReadbacks(Vec<(Entity, SegmentationReadback)>);
+
+#[derive(RenderLabel, Clone, PartialEq, Eq, Hash, Debug)]
+struct SegmentationCopyLabel;
+
+/// Copies every segmentation camera's image into its readback buffer once
+/// all cameras have rendered.
+struct SegmentationCopyNode;
+
+/// Bytes per row of `width` RGBA8 pixels in a texture-to-buffer copy, which
+/// pads rows to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+fn padded_bytes_per_row(width: u32) -> usize {
+    RenderDevice::align_copy_bytes_per_row(width as usize * 4)
+}
+
+/// Spawns a `SegmentationCamera` rendering `size` pixels, placed at the
+/// origin. Parent it to another entity to move it along.
+pub fn spawn_segmentation_camera(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    render_device: &RenderDevice,
+    size: UVec2,
+) -> Entity {
+    let extent = Extent3d {
+        width: size.x,
+        height: size.y,
+        ..default()
+    };
+    let mut image = Image::new_fill(
+        extent,
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage |=
+        TextureUsages::COPY_SRC | TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING;
+    let image = images.add(image);
+
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("segmentation_readback"),
+        size: (padded_bytes_per_row(size.x) * size.y as usize) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    commands
+        .spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    target: RenderTarget::Image(image.clone()),
+                    clear_color: ClearColorConfig::Custom(Color::BLACK),
+                    ..default()
+                },
+                tonemapping: Tonemapping::None,
+                deband_dither: DebandDither::Disabled,
+                ..default()
+            },
+            RenderLayers::layer(SEGMENTATION_LAYER),
+            SegmentationCamera {
+                image: image.clone(),
+            },
+            SegmentationReadback { image, buffer },
+            SegmentationFrame::default(),
+        ))
+        .id()
+}
+
+/// Puts the configured segmentation camera on the main camera once it
+/// exists.
+fn attach_segmentation_camera(
+    mut commands: Commands,
+    config: Res<SegmentationCameraConfig>,
+    render_device: Option<Res<RenderDevice>>,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<Entity, MainCamera>,
+    mut attached: Local<bool>,
+) {
+    let (Some(size), Some(render_device)) = (config.size, render_device) else {
+        return;
+    };
+    if *attached {
+        return;
+    }
+    let Some(main_camera) = cameras.iter().next() else {
+        return;
+    };
+
+    let camera = spawn_segmentation_camera(&mut commands, &mut images, &render_device, size);
+    commands.entity(main_camera).add_child(camera);
+    *attached = true;
+    info!("Segmentation camera rendering {}x{} pixels", size.x, size.y);
+}
+
+fn extract_segmentation_readbacks(
+    mut readbacks: ResMut<SegmentationReadbacks>,
+    cameras: Extract<Query<(Entity, &SegmentationReadback)>>,
+) {
+    readbacks.0 = cameras
+        .iter()
+        .map(|(camera, readback)| (camera, readback.clone()))
+        .collect();
+}
+
+impl Node for SegmentationCopyNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let readbacks = world.resource::<SegmentationReadbacks>();
+        let gpu_images = world.resource::<RenderAssets<GpuImage>>();
+
+        for (_, readback) in &readbacks.0 {
+            let Some(image) = gpu_images.get(&readback.image) else {
+                continue;
+            };
+
+            let mut encoder = render_context
+                .render_device()
+                .create_command_encoder(&CommandEncoderDescriptor::default());
+            encoder.copy_texture_to_buffer(
+                image.texture.as_image_copy(),
+                ImageCopyBuffer {
+                    buffer: &readback.buffer,
+                    layout: ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(padded_bytes_per_row(image.size.x) as u32),
+                        rows_per_image: None,
+                    },
+                },
+                Extent3d {
+                    width: image.size.x,
+                    height: image.size.y,
+                    depth_or_array_layers: 1,
+                },
+            );
+            world
+                .resource::<RenderQueue>()
+                .submit(std::iter::once(encoder.finish()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps each readback buffer once the copy has finished and sends its bytes
+/// to the main world. This waits for the GPU, so it stalls the frame.
+fn read_back_segmentation_frames(
+    readbacks: Res<SegmentationReadbacks>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    render_device: Res<RenderDevice>,
+    sender: Res<SegmentationFrameSender>,
+) {
+    for (camera, readback) in &readbacks.0 {
+        // Nothing was copied before the image reached the GPU.
+        if gpu_images.get(&readback.image).is_none() {
+            continue;
+        }
+
+        let slice = readback.buffer.slice(..);
+        let (mapped, mapping) = mpsc::channel();
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = mapped.send(result);
+        });
+        render_device.poll(Maintain::wait());
+        match mapping.recv() {
+            Ok(Ok(())) => {
+                let _ = sender.0.send((*camera, slice.get_mapped_range().to_vec()));
+                readback.buffer.unmap();
+            }
+            Ok(Err(err)) => error!("Failed to read back segmentation frame: {err}"),
+            Err(_) => error!("Segmentation frame readback was dropped"),
+        }
+    }
+}
+
+/// Strips the row padding off frames from the render world and stores them
+/// on their cameras.
+fn receive_segmentation_frames(
+    receiver: Res<SegmentationFrameReceiver>,
+    mut cameras: Query<(&SegmentationCamera, &mut SegmentationFrame)>,
+    images: Res<Assets<Image>>,
+) {
+    let Ok(receiver) = receiver.0.lock() else {
+        return;
+    };
+
+    for (camera, data) in receiver.try_iter() {
+        let Ok((segmentation, mut frame)) = cameras.get_mut(camera) else {
+            continue;
+        };
+        let Some(image) = images.get(&segmentation.image) else {
+            continue;
+        };
+
+        let size = image.size();
+        let row_bytes = size.x as usize * 4;
+        frame.size = size;
+        frame.data = data
+            .chunks(padded_bytes_per_row(size.x))
+            .take(size.y as usize)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect();
+    }
+}
+
+/// Writes each segmentation camera's latest frame next to the F12
+/// screenshots when F11 is pressed.
+fn save_segmentation_frame(
+    keys: Res<ButtonInput<KeyCode>>,
+    frame_count: Res<FrameCount>,
+    settings: Res<ScreenshotSettings>,
+    cameras: Query<(Entity, &SegmentationFrame)>,
+) {
+    if !keys.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    if let Err(err) = std::fs::create_dir_all(&settings.directory) {
+        error!(
+            "Failed to create screenshot directory {}: {err}",
+            settings.directory.display()
+        );
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    for (camera, frame) in cameras.iter() {
+        if frame.data.is_empty() {
+            warn!("Segmentation camera {camera:?} has not rendered a frame yet");
+            continue;
+        }
+        let mask = Image::new(
+            Extent3d {
+                width: frame.size.x,
+                height: frame.size.y,
+                ..default()
+            },
+            TextureDimension::D2,
+            frame.data.clone(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        let mask = match mask.try_into_dynamic() {
+            Ok(mask) => mask,
+            Err(err) => {
+                error!("Failed to convert segmentation mask: {err}");
+                continue;
+            }
+        };
+        let path = settings.directory.join(format!(
+            "segmentation-{timestamp}-{}-{}.png",
+            frame_count.0,
+            camera.index()
+        ));
+        match mask.save(&path) {
+            Ok(()) => info!("Saved segmentation mask to {}", path.display()),
+            Err(err) => error!("Failed to save segmentation mask: {err}"),
+        }
+    }
+}
+
+fn toggle_segmentation_view(keys: Res<ButtonInput<KeyCode>>, mut view: ResMut<SegmentationView>) {
+    if keys.just_pressed(KeyCode::KeyL) {
+        view.0 = !view.0;
+        info!(
+            "Segmentation view {}",
+            if view.0 { "enabled" } else { "disabled" }
+        );
+    }
+}
+
+/// Points the camera at the segmentation layer with a black background and
+/// no tonemapping or dithering, so the rendered colors match
+/// `segmentation_color` exactly, or back at the regular scene.
+fn apply_segmentation_view(
+    mut commands: Commands,
+    view: Res<SegmentationView>,
+    mut environment: ResMut<WorldEnvironment>,
+    mut cameras: Query<(Entity, &mut Camera), MainCamera>,
+) {
+    for (entity, mut camera) in cameras.iter_mut() {
+        if view.0 {
+            camera.clear_color = ClearColorConfig::Custom(Color::BLACK);
+            commands
+                .entity(entity)
+                .insert((
+                    RenderLayers::layer(SEGMENTATION_LAYER),
+                    Tonemapping::None,
+                    DebandDither::Disabled,
+                ))
+                .remove::<Skybox>();
+        } else {
+            camera.clear_color = ClearColorConfig::Default;
+            commands.entity(entity).insert((
+                RenderLayers::default(),
+                Tonemapping::default(),
+                DebandDither::Enabled,
+            ));
+        }
+    }
+
+    // Puts back the skybox, if the background has one.
+    if !view.0 {
+        environment.set_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::ecs::world::CommandQueue;
+    use bevy::window::ExitCondition;
+
+    use super::*;
+    use crate::inertia::InvalidInertia;
+    use crate::robot::{spawn_robot_from_str, MassOverrides, RobotPlugin};
+
+    #[test]
+    fn despawned_links_give_up_their_ids() {
+        let mut world = World::new();
+        world.init_resource::<SegmentationIds>();
+        world.init_resource::<SegmentationMaterials>();
+        let kept = world.spawn(RobotLink).id();
+        let despawned = world.spawn(RobotLink).id();
+        world.resource_mut::<SegmentationIds>().0 = HashMap::from([(kept, 1), (despawned, 2)]);
+
+        world.despawn(despawned);
+        world.run_system_once(prune_segmentation_links);
+        assert_eq!(
+            world.resource::<SegmentationIds>().0,
+            HashMap::from([(kept, 1)])
+        );
+    }
+
+    const TWO_BOX_URDF: &str = r#"
+        <robot name="boxes">
+          <link name="base">
+            <visual><geometry><box size="0.2 0.2 0.2"/></geometry></visual>
+          </link>
+          <link name="top">
+            <visual><geometry><box size="0.2 0.2 0.2"/></geometry></visual>
+          </link>
+          <joint name="stack" type="fixed">
+            <parent link="base"/>
+            <child link="top"/>
+            <origin xyz="0 0.3 0"/>
+          </joint>
+        </robot>
+    "#;
+
+    #[test]
+    fn camera_sees_each_link_in_its_own_color() {
+        let mut app = App::new();
+        app.add_plugins(
+            DefaultPlugins
+                .build()
+                .disable::<bevy::winit::WinitPlugin>()
+                .disable::<bevy::log::LogPlugin>()
+                .disable::<bevy::audio::AudioPlugin>()
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                }),
+        )
+        .add_plugins((RobotPlugin, SegmentationPlugin))
+        .insert_resource(Msaa::Off)
+        .init_resource::<WorldEnvironment>()
+        .init_resource::<ScreenshotSettings>();
+        app.finish();
+        app.cleanup();
+
+        let world = app.world_mut();
+        let render_device = world.resource::<RenderDevice>().clone();
+        let mut queue = CommandQueue::default();
+        let camera = world.resource_scope(|world, mut images: Mut<Assets<Image>>| {
+            let mut commands = Commands::new(&mut queue, world);
+            spawn_robot_from_str(
+                &mut commands,
+                TWO_BOX_URDF,
+                Path::new(""),
+                Transform::IDENTITY,
+                InvalidInertia::Warn,
+                &MassOverrides::default(),
+            )
+            .unwrap();
+            let camera = spawn_segmentation_camera(
+                &mut commands,
+                &mut images,
+                &render_device,
+                UVec2::new(64, 64),
+            );
+            commands.entity(camera).insert(
+                Transform::from_xyz(0.0, 0.15, 1.5).looking_at(Vec3::new(0.0, 0.15, 0.0), Vec3::Y),
+            );
+            camera
+        });
+        queue.apply(world);
+
+        let mut colors = 0;
+        // Pipelines compile in the background, so the links show up after a
+        // few frames.
+        for _ in 0..500 {
+            app.update();
+            let frame = app.world().get::<SegmentationFrame>(camera).unwrap();
+            colors = frame.data.chunks(4).collect::<HashSet<_>>().len();
+            if colors == 3 {
+                break;
+            }
+        }
+        assert_eq!(colors, 3, "background and two links");
+    }
+}
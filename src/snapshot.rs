@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::robot::{RobotHandle, RobotJoint};
+use crate::SpawnedRobots;
+
+/// Saves the scene's robot state with F6 and restores it with F7.
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SnapshotSettings>()
+            .add_systems(Update, snapshot_keys);
+    }
+}
+
+/// File F6 writes and F7 reads.
+#[derive(Resource)]
+pub struct SnapshotSettings {
+    pub path: PathBuf,
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("snapshot.json"),
+        }
+    }
+}
+
+/// One entry per spawned robot, in `SpawnedRobots` order, so copies of the
+/// same URDF keep their own state. Links are posed kinematically, so this is
+/// the whole robot state; there are no velocities to save.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Snapshot {
+    pub robots: Vec<RobotState>,
+}
+
+/// A robot's root transform, its link transforms keyed by link name, and its
+/// joint positions keyed by `RobotJoint::name`.
+#[derive(Serialize, Deserialize)]
+pub struct RobotState {
+    pub name: String,
+    pub root: TransformState,
+    pub links: BTreeMap<String, TransformState>,
+    pub joints: BTreeMap<String, f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TransformState {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl From<&Transform> for TransformState {
+    fn from(transform: &Transform) -> Self {
+        Self {
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            scale: transform.scale.to_array(),
+        }
+    }
+}
+
+impl From<&TransformState> for Transform {
+    fn from(state: &TransformState) -> Self {
+        Self {
+            translation: Vec3::from_array(state.translation),
+            rotation: Quat::from_array(state.rotation),
+            scale: Vec3::from_array(state.scale),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "snapshot file error: {err}"),
+            SnapshotError::Json(err) => write!(f, "invalid snapshot: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            SnapshotError::Json(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(err: serde_json::Error) -> Self {
+        SnapshotError::Json(err)
+    }
+}
+
+/// Writes the state of every robot in `SpawnedRobots` to `path` as JSON.
+pub fn save_state(world: &World, path: &Path) -> Result<(), SnapshotError> {
+    let snapshot = capture(world);
+    std::fs::write(path, serde_json::to_string_pretty(&snapshot)?)?;
+    Ok(())
+}
+
+/// Restores state written by [`save_state`], robot by robot in spawn order.
+/// Links and joints missing from the snapshot are left where they are.
+pub fn load_state(world: &mut World, path: &Path) -> Result<(), SnapshotError> {
+    let snapshot: Snapshot = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    restore(world, &snapshot);
+    Ok(())
+}
+
+fn capture(world: &World) -> Snapshot {
+    let robots = world.resource::<SpawnedRobots>();
+    let robots = robots
+        .0
+        .iter()
+        .filter_map(|robot| {
+            let name = world.get::<Name>(robot.root)?;
+            let root = world.get::<Transform>(robot.root)?;
+
+            let mut links = BTreeMap::new();
+            let mut joints = BTreeMap::new();
+            for (link, &entity) in &robot.links {
+                if let Some(transform) = world.get::<Transform>(entity) {
+                    links.insert(link.clone(), TransformState::from(transform));
+                }
+                if let Some(joint) = world.get::<RobotJoint>(entity) {
+                    joints.insert(joint.name.clone(), joint.position);
+                }
+            }
+
+            Some(RobotState {
+                name: name.to_string(),
+                root: TransformState::from(root),
+                links,
+                joints,
+            })
+        })
+        .collect();
+
+    Snapshot { robots }
+}
+
+fn restore(world: &mut World, snapshot: &Snapshot) {
+    let robots: Vec<RobotHandle> = world.resource::<SpawnedRobots>().0.clone();
+    if robots.len() != snapshot.robots.len() {
+        warn!(
+            "Snapshot has {} robots but {} are spawned, restoring the first {}",
+            snapshot.robots.len(),
+            robots.len(),
+            robots.len().min(snapshot.robots.len())
+        );
+    }
+
+    for (robot, state) in robots.iter().zip(&snapshot.robots) {
+        if world
+            .get::<Name>(robot.root)
+            .is_some_and(|name| name.as_str() != state.name)
+        {
+            warn!("Restoring robot `{}` onto a different robot", state.name);
+        }
+        if let Some(mut transform) = world.get_mut::<Transform>(robot.root) {
+            *transform = Transform::from(&state.root);
+        }
+
+        for (link, &entity) in &robot.links {
+            if let Some(link_state) = state.links.get(link) {
+                if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+                    *transform = Transform::from(link_state);
+                }
+            }
+            if let Some(mut joint) = world.get_mut::<RobotJoint>(entity) {
+                if let Some(&position) = state.joints.get(&joint.name) {
+                    joint.position = position;
+                }
+            }
+        }
+    }
+}
+
+fn snapshot_keys(world: &mut World) {
+    let keys = world.resource::<ButtonInput<KeyCode>>();
+    let save = keys.just_pressed(KeyCode::F6);
+    let load = keys.just_pressed(KeyCode::F7);
+    if !save && !load {
+        return;
+    }
+
+    let path = world.resource::<SnapshotSettings>().path.clone();
+    if save {
+        match save_state(world, &path) {
+            Ok(()) => info!("Saved snapshot to {}", path.display()),
+            Err(err) => error!("Failed to save snapshot: {err}"),
+        }
+    }
+    if load {
+        match load_state(world, &path) {
+            Ok(()) => info!("Loaded snapshot from {}", path.display()),
+            Err(err) => error!("Failed to load snapshot: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use urdf_rs::JointType;
+
+    use super::*;
+
+    /// Spawns a robot named `arm` with one jointed link, the way copies from
+    /// `--count` share names.
+    fn spawn_arm(world: &mut World, x: f32) -> RobotHandle {
+        let root = world
+            .spawn((Name::new("arm"), Transform::from_xyz(x, 0.0, 0.0)))
+            .id();
+        let link = world
+            .spawn((
+                Name::new("arm/upper"),
+                Transform::from_xyz(0.0, 0.1, 0.0),
+                RobotJoint {
+                    name: "arm/elbow".to_string(),
+                    joint_type: JointType::Revolute,
+                    axis: Vec3::Z,
+                    lower: -1.0,
+                    upper: 1.0,
+                    position: x / 10.0,
+                },
+            ))
+            .id();
+        RobotHandle {
+            root,
+            links: HashMap::from([("upper".to_string(), link)]),
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_copies_apart() {
+        let mut world = World::new();
+        let robots = vec![spawn_arm(&mut world, 1.0), spawn_arm(&mut world, 2.0)];
+        world.insert_resource(SpawnedRobots(robots.clone()));
+        let path = std::env::temp_dir().join(format!("snapshot-{}.json", std::process::id()));
+
+        save_state(&world, &path).unwrap();
+        for robot in &robots {
+            *world.get_mut::<Transform>(robot.root).unwrap() = Transform::IDENTITY;
+            world
+                .get_mut::<RobotJoint>(robot.links["upper"])
+                .unwrap()
+                .position = 0.0;
+        }
+        load_state(&mut world, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for (robot, x) in robots.iter().zip([1.0, 2.0]) {
+            let root = world.get::<Transform>(robot.root).unwrap();
+            assert_eq!(root.translation, Vec3::new(x, 0.0, 0.0));
+            let joint = world.get::<RobotJoint>(robot.links["upper"]).unwrap();
+            assert_eq!(joint.position, x / 10.0);
+        }
+    }
+}
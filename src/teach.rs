@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::robot::RobotJoint;
+
+/// Teach and repeat: K records the current joint positions as a waypoint, P
+/// plays the recording back and Backspace clears it. F8 saves the recording
+/// and F9 loads it.
+pub struct TeachPlugin;
+
+impl Plugin for TeachPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TeachSettings>()
+            .init_resource::<Teach>()
+            .add_systems(
+                Update,
+                (teach_keys, record_waypoint, play_trajectory).chain(),
+            );
+    }
+}
+
+/// File F8 writes and F9 reads.
+#[derive(Resource)]
+pub struct TeachSettings {
+    pub path: PathBuf,
+}
+
+impl Default for TeachSettings {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("trajectory.json"),
+        }
+    }
+}
+
+/// Joint positions keyed by `RobotJoint::name`, reached `time` seconds after
+/// the first waypoint.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Waypoint {
+    pub time: f32,
+    pub joints: BTreeMap<String, f32>,
+}
+
+/// Waypoints in time order. Between two waypoints every joint they share
+/// moves linearly.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Trajectory {
+    pub waypoints: Vec<Waypoint>,
+}
+
+impl Trajectory {
+    pub fn duration(&self) -> f32 {
+        self.waypoints.last().map_or(0.0, |waypoint| waypoint.time)
+    }
+
+    /// Joint positions `time` seconds into the trajectory, held at the first
+    /// and last waypoints outside of it.
+    pub fn sample(&self, time: f32) -> BTreeMap<String, f32> {
+        let next = self
+            .waypoints
+            .iter()
+            .position(|waypoint| waypoint.time > time);
+        let (from, to) = match next {
+            None => {
+                return self
+                    .waypoints
+                    .last()
+                    .map(|waypoint| waypoint.joints.clone())
+                    .unwrap_or_default()
+            }
+            Some(0) => return self.waypoints[0].joints.clone(),
+            Some(i) => (&self.waypoints[i - 1], &self.waypoints[i]),
+        };
+
+        let t = (time - from.time) / (to.time - from.time);
+        from.joints
+            .iter()
+            .map(|(name, &start)| {
+                let end = to.joints.get(name).copied().unwrap_or(start);
+                (name.clone(), start + (end - start) * t)
+            })
+            .collect()
+    }
+}
+
+/// The trajectory being recorded or played back.
+#[derive(Resource, Default)]
+pub struct Teach {
+    pub trajectory: Trajectory,
+    /// `Time::elapsed_seconds` when the first waypoint was recorded.
+    recording_started: Option<f32>,
+    /// Seconds into the trajectory while it plays back.
+    playback: Option<f32>,
+}
+
+#[derive(Debug)]
+pub enum TrajectoryError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl std::fmt::Display for TrajectoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrajectoryError::Io(err) => write!(f, "trajectory file error: {err}"),
+            TrajectoryError::Json(err) => write!(f, "invalid trajectory: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TrajectoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TrajectoryError::Io(err) => Some(err),
+            TrajectoryError::Json(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for TrajectoryError {
+    fn from(err: std::io::Error) -> Self {
+        TrajectoryError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for TrajectoryError {
+    fn from(err: serde_json::Error) -> Self {
+        TrajectoryError::Json(err)
+    }
+}
+
+pub fn save_trajectory(trajectory: &Trajectory, path: &Path) -> Result<(), TrajectoryError> {
+    std::fs::write(path, serde_json::to_string_pretty(trajectory)?)?;
+    Ok(())
+}
+
+pub fn load_trajectory(path: &Path) -> Result<Trajectory, TrajectoryError> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+fn teach_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Res<TeachSettings>,
+    mut teach: ResMut<Teach>,
+) {
+    if keys.just_pressed(KeyCode::KeyP) {
+        if teach.playback.is_some() {
+            teach.playback = None;
+            info!("Stopped playback");
+        } else if teach.trajectory.waypoints.is_empty() {
+            warn!("No waypoints to play back");
+        } else {
+            teach.playback = Some(0.0);
+            info!(
+                "Playing back {} waypoints over {:.1} s",
+                teach.trajectory.waypoints.len(),
+                teach.trajectory.duration()
+            );
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Backspace) {
+        *teach = Teach::default();
+        info!("Cleared recorded waypoints");
+    }
+
+    if keys.just_pressed(KeyCode::F8) {
+        match save_trajectory(&teach.trajectory, &settings.path) {
+            Ok(()) => info!("Saved trajectory to {}", settings.path.display()),
+            Err(err) => error!("Failed to save trajectory: {err}"),
+        }
+    }
+    if keys.just_pressed(KeyCode::F9) {
+        match load_trajectory(&settings.path) {
+            Ok(trajectory) => {
+                info!(
+                    "Loaded {} waypoints from {}",
+                    trajectory.waypoints.len(),
+                    settings.path.display()
+                );
+                *teach = Teach {
+                    trajectory,
+                    ..default()
+                };
+            }
+            Err(err) => error!("Failed to load trajectory: {err}"),
+        }
+    }
+}
+
+fn record_waypoint(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut teach: ResMut<Teach>,
+    joints: Query<&RobotJoint>,
+) {
+    if !keys.just_pressed(KeyCode::KeyK) || teach.playback.is_some() {
+        return;
+    }
+
+    let now = time.elapsed_seconds();
+    // Waypoints recorded after loading a trajectory carry on from its end.
+    let duration = teach.trajectory.duration();
+    let started = *teach.recording_started.get_or_insert(now - duration);
+    let joints = joints
+        .iter()
+        .filter(|joint| joint.is_movable())
+        .map(|joint| (joint.name.clone(), joint.position))
+        .collect();
+    teach.trajectory.waypoints.push(Waypoint {
+        time: now - started,
+        joints,
+    });
+    info!(
+        "Recorded waypoint {} at {:.1} s",
+        teach.trajectory.waypoints.len(),
+        now - started
+    );
+}
+
+fn play_trajectory(time: Res<Time>, mut teach: ResMut<Teach>, mut joints: Query<&mut RobotJoint>) {
+    let Some(elapsed) = teach.playback else {
+        return;
+    };
+
+    let positions = teach.trajectory.sample(elapsed);
+    for mut joint in &mut joints {
+        if let Some(&position) = positions.get(&joint.name) {
+            if joint.position != position {
+                joint.position = position;
+            }
+        }
+    }
+
+    if elapsed >= teach.trajectory.duration() {
+        teach.playback = None;
+        info!("Playback finished");
+    } else {
+        teach.playback = Some(elapsed + time.delta_seconds());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waypoint(time: f32, joints: &[(&str, f32)]) -> Waypoint {
+        Waypoint {
+            time,
+            joints: joints
+                .iter()
+                .map(|&(name, position)| (name.to_string(), position))
+                .collect(),
+        }
+    }
+
+    fn trajectory() -> Trajectory {
+        Trajectory {
+            waypoints: vec![
+                waypoint(0.0, &[("shoulder", 0.0), ("elbow", 1.0)]),
+                waypoint(2.0, &[("shoulder", 1.0)]),
+            ],
+        }
+    }
+
+    #[test]
+    fn holds_first_waypoint_before_start() {
+        let positions = trajectory().sample(-1.0);
+        assert_eq!(positions["shoulder"], 0.0);
+        assert_eq!(positions["elbow"], 1.0);
+    }
+
+    #[test]
+    fn interpolates_between_waypoints() {
+        assert_eq!(trajectory().sample(0.5)["shoulder"], 0.25);
+    }
+
+    #[test]
+    fn holds_last_waypoint_after_end() {
+        let positions = trajectory().sample(3.0);
+        assert_eq!(positions["shoulder"], 1.0);
+        assert!(!positions.contains_key("elbow"));
+    }
+
+    #[test]
+    fn holds_joint_missing_from_next_waypoint() {
+        assert_eq!(trajectory().sample(1.0)["elbow"], 1.0);
+    }
+
+    #[test]
+    fn save_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("trajectory-{}.json", std::process::id()));
+        save_trajectory(&trajectory(), &path).unwrap();
+        let loaded = load_trajectory(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.waypoints.len(), 2);
+        assert_eq!(loaded.waypoints[1].time, 2.0);
+        assert_eq!(loaded.waypoints[0].joints, trajectory().waypoints[0].joints);
+    }
+}
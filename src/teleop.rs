@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+
+use crate::robot::Robot;
+
+pub struct BaseTeleopPlugin;
+
+impl Plugin for BaseTeleopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BaseTeleop>()
+            .add_systems(Update, (toggle_base_teleop, drive_base).chain());
+    }
+}
+
+/// Drives a robot root around with WASD and turns it with Q/E. Toggled with
+/// T. Defaults to the first spawned robot when no target is set. Only active
+/// while the cursor is released, which is when the flycam ignores WASD.
+#[derive(Resource)]
+pub struct BaseTeleop {
+    pub enabled: bool,
+    pub target: Option<Entity>,
+    /// Metres per second.
+    pub speed: f32,
+    /// Radians per second.
+    pub turn_speed: f32,
+}
+
+impl Default for BaseTeleop {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: None,
+            speed: 0.3,
+            turn_speed: 1.0,
+        }
+    }
+}
+
+fn toggle_base_teleop(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut teleop: ResMut<BaseTeleop>,
+    robots: Query<Entity, With<Robot>>,
+) {
+    if teleop.target.is_none() {
+        teleop.target = robots.iter().next();
+    }
+
+    if keys.just_pressed(KeyCode::KeyT) {
+        teleop.enabled = !teleop.enabled;
+        info!(
+            "Base teleop {}",
+            if teleop.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+}
+
+fn drive_base(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    teleop: Res<BaseTeleop>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    mut robots: Query<&mut Transform, With<Robot>>,
+) {
+    if !teleop.enabled {
+        return;
+    }
+
+    let cursor_grabbed = primary_window
+        .get_single()
+        .is_ok_and(|window| window.cursor.grab_mode != CursorGrabMode::None);
+    if cursor_grabbed {
+        return;
+    }
+
+    let Some(mut transform) = teleop.target.and_then(|target| robots.get_mut(target).ok()) else {
+        return;
+    };
+
+    // URDF bases face along their local +X; drive along that heading,
+    // flattened onto the ground plane.
+    let forward = (transform.rotation * Vec3::X)
+        .with_y(0.0)
+        .normalize_or_zero();
+    let left = Vec3::Y.cross(forward);
+
+    let mut direction = Vec3::ZERO;
+    let mut turn = 0.0;
+    if keys.pressed(KeyCode::KeyW) {
+        direction += forward;
+    }
+    if keys.pressed(KeyCode::KeyS) {
+        direction -= forward;
+    }
+    if keys.pressed(KeyCode::KeyA) {
+        direction += left;
+    }
+    if keys.pressed(KeyCode::KeyD) {
+        direction -= left;
+    }
+    if keys.pressed(KeyCode::KeyQ) {
+        turn += 1.0;
+    }
+    if keys.pressed(KeyCode::KeyE) {
+        turn -= 1.0;
+    }
+
+    let dt = time.delta_seconds();
+    transform.translation += direction.normalize_or_zero() * teleop.speed * dt;
+    transform.rotate_y(turn * teleop.turn_speed * dt);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn w_drives_base_forward() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        // Turned a quarter left, the base's +X heading points along -Z.
+        let robot = world
+            .spawn((
+                Robot,
+                Transform::from_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2)),
+            ))
+            .id();
+        world.insert_resource(BaseTeleop {
+            enabled: true,
+            target: Some(robot),
+            ..default()
+        });
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::KeyW);
+        world.insert_resource(keys);
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(2));
+        world.run_system_once(drive_base);
+
+        let translation = world.get::<Transform>(robot).unwrap().translation;
+        assert!(translation.abs_diff_eq(Vec3::new(0.0, 0.0, -0.6), 1e-5));
+    }
+
+    #[test]
+    fn disabled_teleop_leaves_base_alone() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let robot = world.spawn((Robot, Transform::IDENTITY)).id();
+        world.insert_resource(BaseTeleop {
+            target: Some(robot),
+            ..default()
+        });
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::KeyW);
+        world.insert_resource(keys);
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(2));
+        world.run_system_once(drive_base);
+
+        assert_eq!(*world.get::<Transform>(robot).unwrap(), Transform::IDENTITY);
+    }
+}
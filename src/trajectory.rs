@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::robot::RobotJoint;
+use crate::teach::{load_trajectory, Trajectory};
+
+/// Plays a trajectory file, as saved by teach and repeat, back on the robot
+/// joints over sim time, starting when the app starts.
+pub struct JointTrajectoryPlugin;
+
+impl Plugin for JointTrajectoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JointTrajectorySettings>()
+            .add_systems(Startup, load_joint_trajectory)
+            .add_systems(
+                Update,
+                play_joint_trajectory.run_if(resource_exists::<JointTrajectory>),
+            );
+    }
+}
+
+/// Trajectory file to play, and whether to start over after its last
+/// waypoint instead of holding it.
+#[derive(Resource, Default)]
+pub struct JointTrajectorySettings {
+    pub path: Option<PathBuf>,
+    pub looping: bool,
+}
+
+/// How joints move between two waypoints.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+}
+
+/// The trajectory being played back.
+#[derive(Resource)]
+pub struct JointTrajectory {
+    pub trajectory: Trajectory,
+    pub interpolation: Interpolation,
+    pub looping: bool,
+    /// `Time::elapsed_seconds` when playback started.
+    started: Option<f32>,
+}
+
+impl JointTrajectory {
+    pub fn new(trajectory: Trajectory, interpolation: Interpolation, looping: bool) -> Self {
+        Self {
+            trajectory,
+            interpolation,
+            looping,
+            started: None,
+        }
+    }
+
+    /// Joint positions `time` seconds into playback.
+    pub fn sample(&self, time: f32) -> BTreeMap<String, f32> {
+        let duration = self.trajectory.duration();
+        let time = if self.looping && duration > 0.0 {
+            time.rem_euclid(duration)
+        } else {
+            time
+        };
+
+        match self.interpolation {
+            Interpolation::Linear => self.trajectory.sample(time),
+        }
+    }
+}
+
+fn load_joint_trajectory(mut commands: Commands, settings: Res<JointTrajectorySettings>) {
+    let Some(path) = &settings.path else {
+        return;
+    };
+
+    match load_trajectory(path) {
+        Ok(trajectory) => {
+            info!(
+                "Playing {} waypoints from {}",
+                trajectory.waypoints.len(),
+                path.display()
+            );
+            commands.insert_resource(JointTrajectory::new(
+                trajectory,
+                Interpolation::default(),
+                settings.looping,
+            ));
+        }
+        Err(err) => error!("Failed to load trajectory {}: {err}", path.display()),
+    }
+}
+
+fn play_joint_trajectory(
+    time: Res<Time>,
+    mut player: ResMut<JointTrajectory>,
+    mut joints: Query<&mut RobotJoint>,
+) {
+    let now = time.elapsed_seconds();
+    let started = *player.started.get_or_insert(now);
+
+    let positions = player.sample(now - started);
+    for mut joint in &mut joints {
+        if let Some(&position) = positions.get(&joint.name) {
+            if joint.position != position {
+                joint.position = position;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use urdf_rs::JointType;
+
+    use super::*;
+    use crate::teach::Waypoint;
+
+    fn two_waypoints(looping: bool) -> JointTrajectory {
+        let waypoint = |time, position| Waypoint {
+            time,
+            joints: BTreeMap::from([("arm/elbow".to_string(), position)]),
+        };
+        JointTrajectory::new(
+            Trajectory {
+                waypoints: vec![waypoint(0.0, 0.0), waypoint(2.0, 1.0)],
+            },
+            Interpolation::Linear,
+            looping,
+        )
+    }
+
+    #[test]
+    fn commands_midpoint_between_waypoints() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(two_waypoints(false));
+        let elbow = world
+            .spawn(RobotJoint {
+                name: "arm/elbow".to_string(),
+                joint_type: JointType::Revolute,
+                axis: Vec3::Y,
+                lower: -1.0,
+                upper: 1.0,
+                position: 0.0,
+            })
+            .id();
+
+        world.run_system_once(play_joint_trajectory);
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_system_once(play_joint_trajectory);
+
+        assert_eq!(world.get::<RobotJoint>(elbow).unwrap().position, 0.5);
+    }
+
+    #[test]
+    fn holds_or_loops_at_end() {
+        assert_eq!(two_waypoints(false).sample(2.5)["arm/elbow"], 1.0);
+        assert_eq!(two_waypoints(true).sample(2.5)["arm/elbow"], 0.25);
+    }
+}
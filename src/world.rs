@@ -1,44 +1,247 @@
 use bevy::color;
+use bevy::core_pipeline::Skybox;
 use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
+use bevy::render::texture::ImageSampler;
+
+use crate::camera::MainCamera;
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (spawn_lights, spawn_floor));
+        app.init_resource::<FloorShape>()
+            .init_resource::<WorldEnvironment>()
+            .init_resource::<LightingConfig>()
+            .add_systems(Startup, (spawn_lights, spawn_floor))
+            .add_systems(
+                Update,
+                apply_world_environment.run_if(resource_changed::<WorldEnvironment>),
+            );
     }
 }
 
-fn spawn_lights(mut commands: Commands) {
-    let directional_light = DirectionalLightBundle {
-        directional_light: DirectionalLight {
+/// Directional lights spawned at startup, plus the ambient light level. The
+/// default is a single shadow-casting light from above and slightly behind.
+#[derive(Resource, Clone)]
+pub struct LightingConfig {
+    pub lights: Vec<LightSpec>,
+    pub ambient: f32,
+}
+
+impl Default for LightingConfig {
+    fn default() -> Self {
+        Self {
+            lights: vec![LightSpec::default()],
+            ambient: AmbientLight::default().brightness,
+        }
+    }
+}
+
+/// A directional light shining along `direction`, in world space.
+#[derive(Clone)]
+pub struct LightSpec {
+    pub direction: Vec3,
+    pub illuminance: f32,
+    pub color: Color,
+    pub shadows: bool,
+}
+
+impl Default for LightSpec {
+    fn default() -> Self {
+        Self {
+            direction: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.8) * Vec3::NEG_Z,
             illuminance: 5000.0,
-            shadows_enabled: true,
+            color: Color::WHITE,
+            shadows: true,
+        }
+    }
+}
+
+fn spawn_lights(mut commands: Commands, lighting: Res<LightingConfig>) {
+    commands.insert_resource(AmbientLight {
+        brightness: lighting.ambient,
+        ..Default::default()
+    });
+
+    for light in &lighting.lights {
+        commands.spawn(DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: light.color,
+                illuminance: light.illuminance,
+                shadows_enabled: light.shadows,
+                ..Default::default()
+            },
+            transform: Transform::default().looking_to(light.direction, Vec3::Y),
             ..Default::default()
+        });
+    }
+}
+
+/// What is drawn behind the scene. The default matches Bevy's stock clear
+/// color.
+#[derive(Resource, Clone)]
+pub struct WorldEnvironment {
+    pub background: Background,
+}
+
+impl Default for WorldEnvironment {
+    fn default() -> Self {
+        Self {
+            background: Background::Solid(ClearColor::default().0),
+        }
+    }
+}
+
+/// `Gradient` blends from `bottom` below the horizon to `top` overhead.
+/// `Skybox` loads a cubemap (e.g. a `.ktx2` environment map) through the
+/// asset server.
+#[derive(Clone)]
+pub enum Background {
+    Solid(Color),
+    Gradient { top: Color, bottom: Color },
+    Skybox { path: String, brightness: f32 },
+}
+
+const GRADIENT_FACE_SIZE: u32 = 64;
+const GRADIENT_BRIGHTNESS: f32 = 1000.0;
+
+fn apply_world_environment(
+    mut commands: Commands,
+    environment: Res<WorldEnvironment>,
+    cameras: Query<Entity, MainCamera>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let skybox = match &environment.background {
+        Background::Solid(color) => {
+            commands.insert_resource(ClearColor(*color));
+            None
+        }
+        Background::Gradient { top, bottom } => Some(Skybox {
+            image: images.add(gradient_cubemap(*top, *bottom, GRADIENT_FACE_SIZE)),
+            brightness: GRADIENT_BRIGHTNESS,
+        }),
+        Background::Skybox { path, brightness } => Some(Skybox {
+            image: asset_server.load(path),
+            brightness: *brightness,
+        }),
+    };
+
+    for camera in cameras.iter() {
+        match &skybox {
+            Some(skybox) => commands.entity(camera).insert(skybox.clone()),
+            None => commands.entity(camera).remove::<Skybox>(),
+        };
+    }
+}
+
+/// A cubemap with `size` x `size` faces shaded by how far each texel's
+/// direction points up, from `bottom` straight down to `top` straight up.
+fn gradient_cubemap(top: Color, bottom: Color, size: u32) -> Image {
+    let top = top.to_linear();
+    let bottom = bottom.to_linear();
+
+    let mut data = Vec::with_capacity((size * size * 6 * 4) as usize);
+    // Faces in wgpu cube order: +X, -X, +Y, -Y, +Z, -Z.
+    for face in 0..6 {
+        for y in 0..size {
+            for x in 0..size {
+                let u = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                let v = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                let direction = match face {
+                    0 => Vec3::new(1.0, -v, -u),
+                    1 => Vec3::new(-1.0, -v, u),
+                    2 => Vec3::new(u, 1.0, v),
+                    3 => Vec3::new(u, -1.0, -v),
+                    4 => Vec3::new(u, -v, 1.0),
+                    _ => Vec3::new(-u, -v, -1.0),
+                };
+                let t = direction.normalize().y * 0.5 + 0.5;
+                let color = Color::from(bottom.mix(&top, t));
+                data.extend_from_slice(&color.to_srgba().to_u8_array());
+            }
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
         },
-        transform: Transform::from_rotation(Quat::from_rotation_x(
-            -std::f32::consts::FRAC_PI_2 * 0.8,
-        )),
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
         ..Default::default()
-    };
+    });
+    image
+}
 
-    commands.spawn(directional_light);
+/// Shape of the ground. `Grid` is a square `size` metres across with a
+/// checkerboard of `GRID_CELL_SIZE` cells for judging scale.
+#[derive(Resource, Clone, Copy)]
+pub enum FloorShape {
+    Disk { radius: f32 },
+    Plane,
+    Grid { size: f32 },
 }
 
+impl Default for FloorShape {
+    fn default() -> Self {
+        FloorShape::Disk { radius: 100.0 }
+    }
+}
+
+const GRID_CELL_SIZE: f32 = 0.1;
+const PLANE_HALF_SIZE: f32 = 10_000.0;
+
 fn spawn_floor(
     mut commands: Commands,
+    floor_shape: Res<FloorShape>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
 ) {
+    let grass = color::Color::srgb(0.2 / 1.5, 1.0 / 1.5, 0.4 / 1.5);
+
+    let (mesh, material) = match *floor_shape {
+        FloorShape::Disk { radius } => (
+            Mesh::from(Circle::new(radius)),
+            StandardMaterial {
+                base_color: grass,
+                ..Default::default()
+            },
+        ),
+        FloorShape::Plane => (
+            Mesh::from(Rectangle::new(PLANE_HALF_SIZE * 2.0, PLANE_HALF_SIZE * 2.0)),
+            StandardMaterial {
+                base_color: grass,
+                ..Default::default()
+            },
+        ),
+        FloorShape::Grid { size } => (
+            Mesh::from(Rectangle::new(size, size)),
+            StandardMaterial {
+                base_color_texture: Some(images.add(checkerboard((size / GRID_CELL_SIZE) as u32))),
+                ..Default::default()
+            },
+        ),
+    };
+
     commands
         .spawn(TransformBundle::default())
         .with_children(|parent| {
             parent.spawn(PbrBundle {
-                mesh: meshes.add(Mesh::from(Circle::new(100.0))),
-                material: materials.add(StandardMaterial {
-                    base_color: color::Color::srgb(0.2 / 1.5, 1.0 / 1.5, 0.4 / 1.5),
-                    ..Default::default()
-                }),
+                mesh: meshes.add(mesh),
+                material: materials.add(material),
                 transform: Transform::from_rotation(Quat::from_rotation_x(
                     -std::f32::consts::FRAC_PI_2,
                 )),
@@ -46,3 +249,136 @@ fn spawn_floor(
             });
         });
 }
+
+/// A `cells` x `cells` checkerboard, one texel per cell.
+fn checkerboard(cells: u32) -> Image {
+    let cells = cells.max(1);
+    let mut data = Vec::with_capacity((cells * cells * 4) as usize);
+    for y in 0..cells {
+        for x in 0..cells {
+            let shade = if (x + y) % 2 == 0 { 200 } else { 120 };
+            data.extend_from_slice(&[shade, shade, shade, 255]);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: cells,
+            height: cells,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// An app with the asset resources the world systems use.
+    fn asset_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .init_asset::<Image>();
+        app
+    }
+
+    #[test]
+    fn solid_background_sets_clear_color() {
+        let mut app = asset_app();
+        let world = app.world_mut();
+        let color = Color::srgb(0.1, 0.2, 0.3);
+        world.insert_resource(WorldEnvironment {
+            background: Background::Solid(color),
+        });
+        let skybox = Skybox {
+            image: Handle::default(),
+            brightness: 1000.0,
+        };
+        let camera = world.spawn((Camera3d::default(), skybox)).id();
+        world.run_system_once(apply_world_environment);
+
+        assert_eq!(world.resource::<ClearColor>().0, color);
+        assert!(world.get::<Skybox>(camera).is_none());
+    }
+
+    #[test]
+    fn spawns_one_directional_light_per_spec() {
+        let mut world = World::new();
+        let lights = vec![
+            LightSpec::default(),
+            LightSpec {
+                direction: Vec3::NEG_X,
+                illuminance: 1000.0,
+                color: Color::srgb(1.0, 0.9, 0.8),
+                shadows: false,
+            },
+        ];
+        world.insert_resource(LightingConfig {
+            lights,
+            ambient: 250.0,
+        });
+        world.run_system_once(spawn_lights);
+
+        let mut lights: Vec<(f32, bool, Vec3)> = world
+            .query::<(&DirectionalLight, &Transform)>()
+            .iter(&world)
+            .map(|(light, transform)| {
+                (
+                    light.illuminance,
+                    light.shadows_enabled,
+                    *transform.forward(),
+                )
+            })
+            .collect();
+        lights.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(lights.len(), 2);
+        assert_eq!((lights[0].0, lights[0].1), (1000.0, false));
+        assert!(lights[0].2.abs_diff_eq(Vec3::NEG_X, 1e-5));
+        assert_eq!((lights[1].0, lights[1].1), (5000.0, true));
+        assert_eq!(world.resource::<AmbientLight>().brightness, 250.0);
+    }
+
+    #[test]
+    fn grid_floor_is_checkered_square() {
+        let mut app = asset_app();
+        let world = app.world_mut();
+        world.insert_resource(FloorShape::Grid { size: 2.0 });
+        world.run_system_once(spawn_floor);
+
+        let (mesh, material) = world
+            .query::<(&Handle<Mesh>, &Handle<StandardMaterial>)>()
+            .single(world);
+        let aabb = world
+            .resource::<Assets<Mesh>>()
+            .get(mesh)
+            .unwrap()
+            .compute_aabb()
+            .unwrap();
+        assert_eq!(Vec3::from(aabb.half_extents), Vec3::new(1.0, 1.0, 0.0));
+
+        let texture = world
+            .resource::<Assets<StandardMaterial>>()
+            .get(material)
+            .unwrap()
+            .base_color_texture
+            .clone()
+            .unwrap();
+        let cells = (2.0 / GRID_CELL_SIZE) as u32;
+        let size = world
+            .resource::<Assets<Image>>()
+            .get(&texture)
+            .unwrap()
+            .size();
+        assert_eq!(size, UVec2::splat(cells));
+    }
+}